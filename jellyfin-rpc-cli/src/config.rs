@@ -1,8 +1,9 @@
+use crate::schedule::Schedule;
 use colored::Colorize;
-use jellyfin_rpc::{Button, DisplayFormat, MediaType, VERSION};
+use jellyfin_rpc::{Button, DisplayFormat, MediaType, Profile, TimestampMode, VERSION};
 use log::{debug, warn};
 use serde::{Deserialize, Serialize};
-use std::env;
+use std::{collections::HashMap, env};
 
 /// Main struct containing every other struct in the file.
 ///
@@ -18,6 +19,8 @@ pub struct Config {
     pub imgbb: ImgBB,
     /// Images configuration.
     pub images: Images,
+    /// Schedule configuration.
+    pub schedule: Schedule,
 }
 
 /// This struct contains every "required" part of the config.
@@ -34,8 +37,36 @@ pub struct Jellyfin {
     pub movies: DisplayOptions,
     /// Contains configuration for Episode display.
     pub episodes: DisplayOptions,
+    /// Contains configuration for Book display.
+    pub books: DisplayOptions,
+    /// Contains configuration for Live TV display.
+    pub tv: DisplayOptions,
+    /// Contains configuration for Music Video display.
+    pub music_videos: DisplayOptions,
+    /// Contains configuration for Audiobook display.
+    pub audio_books: DisplayOptions,
+    /// Contains configuration for Video display.
+    pub videos: DisplayOptions,
+    /// Contains configuration for Trailer display.
+    pub trailers: DisplayOptions,
+    /// Contains configuration for Recording display.
+    pub recordings: DisplayOptions,
+    /// Contains configuration for TV Program display.
+    pub tv_programs: DisplayOptions,
+    /// Contains configuration for Photo display.
+    pub photos: DisplayOptions,
     /// Blacklist configuration.
     pub blacklist: Blacklist,
+    /// Spoiler guard configuration.
+    pub spoilers: Spoilers,
+    /// Synced lyrics configuration.
+    pub lyrics: Lyrics,
+    /// Extras and short clips configuration.
+    pub extras: Extras,
+    /// Display used instead of the regular one while a media type is paused
+    pub paused: HashMap<MediaType, DisplayFormat>,
+    /// Activity shown while nothing is playing.
+    pub idle: Idle,
     /// Self signed certificate option
     pub self_signed_cert: bool,
     /// Simple episode name
@@ -44,6 +75,10 @@ pub struct Jellyfin {
     pub append_prefix: bool,
     /// Add a divider between numbers
     pub add_divider: bool,
+    /// Format used by wall-clock placeholders such as `{ends-at}`
+    pub time_format: Option<String>,
+    /// Access token of the Jellyfin client that joins SyncPlay groups, required by `syncplay_join`
+    pub syncplay_token: Option<String>,
 }
 
 /// Contains configuration for Music/Movie display.
@@ -62,6 +97,30 @@ pub struct Discord {
     pub buttons: Option<Vec<Button>>,
     /// Show status when media is paused
     pub show_paused: bool,
+    /// Seconds the position may drift before timestamps are re-based
+    pub timestamp_tolerance: Option<u64>,
+    /// Which timestamps to show for each media type
+    pub timestamp_modes: HashMap<MediaType, TimestampMode>,
+    /// Show the time elapsed since pausing
+    pub show_paused_duration: bool,
+    /// Clear the activity after being paused for this many seconds
+    pub pause_timeout: Option<u64>,
+    /// Show the pause icon as the small image while paused
+    pub show_pause_icon: bool,
+    /// Settings that override the global ones for a media type
+    pub profiles: HashMap<MediaType, Profile>,
+    /// Seconds a media type has to play before switching to the application id of its profile
+    pub application_switch_delay: Option<u64>,
+    /// Scope audiobook timestamps to the current chapter
+    pub audio_book_chapter_timestamps: bool,
+    /// Show the position in the play queue as the party size
+    pub queue_party_size: bool,
+    /// Show the size of the SyncPlay group as the party size
+    pub syncplay_party_size: bool,
+    /// Let friends ask to join the SyncPlay group
+    pub syncplay_join: bool,
+    /// Maximum party size shown with the SyncPlay group, keeps "Ask to Join" available
+    pub syncplay_max_party_size: Option<u32>,
     /// Text when mouse hovers status image
     pub image_text: String,
 }
@@ -85,6 +144,22 @@ pub struct Images {
     pub audio_book_image: Option<String>,
     /// Override the default image when media is a book
     pub book_image: Option<String>,
+    /// Override the default image when media is a music video
+    pub music_video_image: Option<String>,
+    /// Override the default image when media is a video
+    pub video_image: Option<String>,
+    /// Override the default image when media is a trailer
+    pub trailer_image: Option<String>,
+    /// Override the default image when media is a recording
+    pub recording_image: Option<String>,
+    /// Override the default image when media is a tv program
+    pub tv_program_image: Option<String>,
+    /// Override the default image when media is a photo
+    pub photo_image: Option<String>,
+    /// Image shown while browsing Jellyfin
+    pub browsing_image: Option<String>,
+    /// Image shown for the last watched item when it has none
+    pub last_watched_image: Option<String>,
     /// Enables images, not everyone wants them so its a toggle.
     pub enable_images: bool,
     /// Enables imgbb images.
@@ -104,6 +179,7 @@ pub struct ConfigBuilder {
     pub discord: Option<DiscordBuilder>,
     pub imgbb: Option<ImgBB>,
     pub images: Option<ImagesBuilder>,
+    pub schedule: Option<Schedule>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -114,11 +190,28 @@ pub struct JellyfinBuilder {
     pub music: Option<DisplayOptionsBuilder>,
     pub movies: Option<DisplayOptionsBuilder>,
     pub episodes: Option<DisplayOptionsBuilder>,
+    pub books: Option<DisplayOptionsBuilder>,
+    pub tv: Option<DisplayOptionsBuilder>,
+    pub music_videos: Option<DisplayOptionsBuilder>,
+    pub audio_books: Option<DisplayOptionsBuilder>,
+    pub videos: Option<DisplayOptionsBuilder>,
+    pub trailers: Option<DisplayOptionsBuilder>,
+    pub recordings: Option<DisplayOptionsBuilder>,
+    pub tv_programs: Option<DisplayOptionsBuilder>,
+    pub photos: Option<DisplayOptionsBuilder>,
     pub blacklist: Option<Blacklist>,
+    pub spoilers: Option<Spoilers>,
+    pub lyrics: Option<Lyrics>,
+    pub extras: Option<Extras>,
+    #[serde(default, deserialize_with = "media_type_map")]
+    pub paused: Option<HashMap<MediaType, DisplayFormat>>,
+    pub idle: Option<Idle>,
     pub self_signed_cert: Option<bool>,
     pub show_simple: Option<bool>,
     pub append_prefix: Option<bool>,
     pub add_divider: Option<bool>,
+    pub time_format: Option<String>,
+    pub syncplay_token: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -136,6 +229,26 @@ pub struct DisplayOptionsBuilder {
     pub separator: Option<String>,
 }
 
+impl From<Option<DisplayOptionsBuilder>> for DisplayOptions {
+    fn from(builder: Option<DisplayOptionsBuilder>) -> Self {
+        let Some(builder) = builder else {
+            return Self {
+                display: None,
+                separator: None,
+            };
+        };
+
+        Self {
+            display: builder.display.map(|disp| match disp {
+                Display::Vec(display) => DisplayFormat::from(display),
+                Display::String(display) => DisplayFormat::from(display),
+                Display::CustomFormat(display) => display,
+            }),
+            separator: builder.separator,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum Display {
@@ -156,11 +269,73 @@ pub struct Blacklist {
     pub libraries: Option<Vec<String>>,
 }
 
+/// Hide episode titles of series and libraries that friends may not have caught up on.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct Spoilers {
+    /// `Vec<String>` of series to hide episode titles for
+    pub series: Option<Vec<String>>,
+    /// `Vec<String>` of libraries to hide episode titles for
+    pub libraries: Option<Vec<String>>,
+    /// Show the title again once the episode premiered this many days ago
+    pub min_age_days: Option<u64>,
+    /// Text shown instead of the episode title
+    pub mask: Option<String>,
+    /// Use the series image instead of the episode image
+    pub hide_images: Option<bool>,
+}
+
+/// Show the current line of synced lyrics while listening to music.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct Lyrics {
+    /// Replace the music state with the current lyric line
+    pub enabled: Option<bool>,
+    /// Format of the state while a lyric line is shown, `{lyric}` is the line
+    pub format: Option<String>,
+}
+
+/// Keep extras, pre-rolls and short clips from replacing the activity.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct Extras {
+    /// `Vec<String>` of Jellyfin `ExtraType`s to never display
+    pub ignored_types: Option<Vec<String>>,
+    /// Don't display items shorter than this many seconds
+    pub min_runtime: Option<u64>,
+    /// Only display a new item after it played for this many seconds, paused time doesn't count
+    pub min_play_time: Option<u64>,
+}
+
+/// Show what was last watched or that Jellyfin is open while nothing is playing.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct Idle {
+    /// Show "Browsing Jellyfin" while a client is open
+    pub browsing: Option<bool>,
+    /// Display of the browsing activity, supports `{device}` and `{client}`
+    pub browsing_display: Option<DisplayFormat>,
+    /// Keep showing the last item for this many minutes after playback stops
+    pub last_watched_minutes: Option<u64>,
+    /// Display of the last watched activity, `{__default}` is the regular details
+    pub last_watched_display: Option<DisplayFormat>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct DiscordBuilder {
     pub application_id: Option<String>,
     pub buttons: Option<Vec<Button>>,
     pub show_paused: Option<bool>,
+    pub timestamp_tolerance: Option<u64>,
+    #[serde(default, deserialize_with = "media_type_map")]
+    pub timestamp_modes: Option<HashMap<MediaType, TimestampMode>>,
+    pub show_paused_duration: Option<bool>,
+    pub pause_timeout: Option<u64>,
+    pub show_pause_icon: Option<bool>,
+    #[serde(default, deserialize_with = "media_type_map")]
+    pub profiles: Option<HashMap<MediaType, Profile>>,
+    pub application_switch_delay: Option<u64>,
+    pub audio_book_chapter_timestamps: Option<bool>,
+    pub queue_party_size: Option<bool>,
+    pub syncplay_party_size: Option<bool>,
+    pub syncplay_join: Option<bool>,
+    pub syncplay_max_party_size: Option<u32>,
     pub image_text: Option<String>,
 }
 
@@ -183,6 +358,14 @@ pub struct ImagesBuilder {
     pub music_image: Option<String>,
     pub audio_book_image: Option<String>,
     pub book_image: Option<String>,
+    pub music_video_image: Option<String>,
+    pub video_image: Option<String>,
+    pub trailer_image: Option<String>,
+    pub recording_image: Option<String>,
+    pub tv_program_image: Option<String>,
+    pub photo_image: Option<String>,
+    pub browsing_image: Option<String>,
+    pub last_watched_image: Option<String>,
     pub enable_images: Option<bool>,
     pub imgbb_images: Option<bool>,
 }
@@ -234,12 +417,36 @@ pub fn get_config_path() -> Result<String, Box<dyn std::error::Error>> {
     }
 }
 
+/// Deserializes a map keyed by media type, unlike [`MediaType`] itself it rejects
+/// unknown names instead of turning them into [`MediaType::None`]
+fn media_type_map<'de, D, V>(deserializer: D) -> Result<Option<HashMap<MediaType, V>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    V: Deserialize<'de>,
+{
+    let Some(map) = Option::<HashMap<String, V>>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+
+    map.into_iter()
+        .map(|(key, value)| match MediaType::from(key.to_lowercase()) {
+            MediaType::None => Err(serde::de::Error::custom(format!(
+                "unknown media type `{}`",
+                key
+            ))),
+            media_type => Ok((media_type, value)),
+        })
+        .collect::<Result<HashMap<_, _>, _>>()
+        .map(Some)
+}
+
 pub enum ConfigBuilderLoaderError {
     InvalidJellyfinKeyPath,
     InvalidImgBBKeyPath,
     InvalidConfigPath,
-    InvalidConfig,
-    MissingJellyfinKey
+    InvalidConfig(serde_json::Error),
+    MissingJellyfinKey,
+    MissingSyncPlayToken
 }
 
 impl ConfigBuilder {
@@ -252,15 +459,32 @@ impl ConfigBuilder {
                 music: None,
                 movies: None,
                 episodes: None,
+                books: None,
+                tv: None,
+                music_videos: None,
+                audio_books: None,
+                videos: None,
+                trailers: None,
+                recordings: None,
+                tv_programs: None,
+                photos: None,
                 blacklist: None,
+                spoilers: None,
+                lyrics: None,
+                extras: None,
+                paused: None,
+                idle: None,
                 self_signed_cert: None,
                 show_simple: Some(false),
                 append_prefix: Some(false),
                 add_divider: Some(false),
+                time_format: None,
+                syncplay_token: None,
             },
             discord: None,
             imgbb: None,
             images: None,
+            schedule: None,
         }
     }
 
@@ -271,7 +495,7 @@ impl ConfigBuilder {
         let config_data = std::fs::read_to_string(config_path)
             .map_err(|_| ConfigBuilderLoaderError::InvalidConfigPath)?;
         let mut config: ConfigBuilder = serde_json::from_str(&config_data)
-            .map_err(|_| ConfigBuilderLoaderError::InvalidConfig)?;
+            .map_err(ConfigBuilderLoaderError::InvalidConfig)?;
 
         if let Some(p) = jellyfin_key_path {
             debug!("Jellyfin key path is: {}", p);
@@ -310,6 +534,12 @@ impl ConfigBuilder {
             return Err(ConfigBuilderLoaderError::MissingJellyfinKey);
         }
 
+        if config.discord.as_ref().and_then(|discord| discord.syncplay_join).unwrap_or(false)
+            && config.jellyfin.syncplay_token.is_none()
+        {
+            return Err(ConfigBuilderLoaderError::MissingSyncPlayToken);
+        }
+
         debug!("Config loaded successfully");
 
         Ok(config)
@@ -321,66 +551,6 @@ impl ConfigBuilder {
             Username::String(username) => username.split(',').map(|u| u.to_string()).collect(),
         };
 
-        let music_display;
-        let music_separator;
-
-        if let Some(music) = self.jellyfin.music {
-            if let Some(disp) = music.display {
-                music_display = Some(match disp {
-                    Display::Vec(display) => DisplayFormat::from(display),
-                    Display::String(display) => DisplayFormat::from(display),
-                    Display::CustomFormat(display) => display,
-                });
-            } else {
-                music_display = None;
-            }
-
-            music_separator = music.separator;
-        } else {
-            music_display = None;
-            music_separator = None;
-        }
-
-        let movie_display;
-        let movie_separator;
-
-        if let Some(movies) = self.jellyfin.movies {
-            if let Some(disp) = movies.display {
-                movie_display = Some(match disp {
-                    Display::Vec(display) => DisplayFormat::from(display),
-                    Display::String(display) => DisplayFormat::from(display),
-                    Display::CustomFormat(display) => display,
-                });
-            } else {
-                movie_display = None;
-            }
-
-            movie_separator = movies.separator;
-        } else {
-            movie_display = None;
-            movie_separator = None;
-        }
-
-        let episode_display;
-        let episode_separator;
-
-        if let Some(episodes) = self.jellyfin.episodes {
-            if let Some(disp) = episodes.display {
-                episode_display = Some(match disp {
-                    Display::Vec(display) => DisplayFormat::from(display),
-                    Display::String(display) => DisplayFormat::from(display),
-                    Display::CustomFormat(display) => display,
-                });
-            } else {
-                episode_display = None;
-            }
-
-            episode_separator = episodes.separator;
-        } else {
-            episode_display = None;
-            episode_separator = None;
-        }
-
         let media_types;
         let libraries;
 
@@ -395,12 +565,36 @@ impl ConfigBuilder {
         let application_id;
         let buttons;
         let show_paused;
+        let timestamp_tolerance;
+        let timestamp_modes;
+        let show_paused_duration;
+        let pause_timeout;
+        let show_pause_icon;
+        let profiles;
+        let application_switch_delay;
+        let audio_book_chapter_timestamps;
+        let queue_party_size;
+        let syncplay_party_size;
+        let syncplay_join;
+        let syncplay_max_party_size;
         let mut image_text = format!("tests{}", VERSION.unwrap_or("UNKNOWN"));
 
         if let Some(discord) = self.discord {
             application_id = discord.application_id;
             buttons = discord.buttons;
             show_paused = discord.show_paused.unwrap_or(true);
+            timestamp_tolerance = discord.timestamp_tolerance;
+            timestamp_modes = discord.timestamp_modes.unwrap_or_default();
+            show_paused_duration = discord.show_paused_duration.unwrap_or(false);
+            pause_timeout = discord.pause_timeout;
+            show_pause_icon = discord.show_pause_icon.unwrap_or(true);
+            profiles = discord.profiles.unwrap_or_default();
+            application_switch_delay = discord.application_switch_delay;
+            audio_book_chapter_timestamps = discord.audio_book_chapter_timestamps.unwrap_or(false);
+            queue_party_size = discord.queue_party_size.unwrap_or(false);
+            syncplay_party_size = discord.syncplay_party_size.unwrap_or(false);
+            syncplay_join = discord.syncplay_join.unwrap_or(false);
+            syncplay_max_party_size = discord.syncplay_max_party_size;
             if let Some(text) = discord.image_text {
                 image_text = text
                     .replace("{version}", VERSION.unwrap_or("UNKNOWN"));
@@ -409,6 +603,18 @@ impl ConfigBuilder {
             application_id = None;
             buttons = None;
             show_paused = true;
+            timestamp_tolerance = None;
+            timestamp_modes = HashMap::new();
+            show_paused_duration = false;
+            pause_timeout = None;
+            show_pause_icon = true;
+            profiles = HashMap::new();
+            application_switch_delay = None;
+            audio_book_chapter_timestamps = false;
+            queue_party_size = false;
+            syncplay_party_size = false;
+            syncplay_join = false;
+            syncplay_max_party_size = None;
         }
 
         let api_token;
@@ -430,6 +636,14 @@ impl ConfigBuilder {
         let music_image;
         let audio_book_image;
         let book_image;
+        let music_video_image;
+        let video_image;
+        let trailer_image;
+        let recording_image;
+        let tv_program_image;
+        let photo_image;
+        let browsing_image;
+        let last_watched_image;
         let enable_images;
         let imgbb_images;
 
@@ -442,6 +656,14 @@ impl ConfigBuilder {
             music_image = images.music_image;
             audio_book_image = images.audio_book_image;
             book_image = images.book_image;
+            music_video_image = images.music_video_image;
+            video_image = images.video_image;
+            trailer_image = images.trailer_image;
+            recording_image = images.recording_image;
+            tv_program_image = images.tv_program_image;
+            photo_image = images.photo_image;
+            browsing_image = images.browsing_image;
+            last_watched_image = images.last_watched_image;
             enable_images = images.enable_images.unwrap_or(false);
             imgbb_images = images.imgbb_images.unwrap_or(false);
         } else {
@@ -453,6 +675,14 @@ impl ConfigBuilder {
             music_image = None;
             audio_book_image = None;
             book_image = None;
+            music_video_image = None;
+            video_image = None;
+            trailer_image = None;
+            recording_image = None;
+            tv_program_image = None;
+            photo_image = None;
+            browsing_image = None;
+            last_watched_image = None;
             enable_images = false;
             imgbb_images = false;
         }
@@ -470,31 +700,50 @@ impl ConfigBuilder {
                 url,
                 api_key: self.jellyfin.api_key.unwrap_or("".to_string()),
                 username,
-                music: DisplayOptions {
-                    display: music_display,
-                    separator: music_separator,
-                },
-                movies: DisplayOptions {
-                    display: movie_display,
-                    separator: movie_separator,
-                },
-                episodes: DisplayOptions {
-                    display: episode_display,
-                    separator: episode_separator,
-                },
+                music: DisplayOptions::from(self.jellyfin.music),
+                movies: DisplayOptions::from(self.jellyfin.movies),
+                episodes: DisplayOptions::from(self.jellyfin.episodes),
+                books: DisplayOptions::from(self.jellyfin.books),
+                tv: DisplayOptions::from(self.jellyfin.tv),
+                music_videos: DisplayOptions::from(self.jellyfin.music_videos),
+                audio_books: DisplayOptions::from(self.jellyfin.audio_books),
+                videos: DisplayOptions::from(self.jellyfin.videos),
+                trailers: DisplayOptions::from(self.jellyfin.trailers),
+                recordings: DisplayOptions::from(self.jellyfin.recordings),
+                tv_programs: DisplayOptions::from(self.jellyfin.tv_programs),
+                photos: DisplayOptions::from(self.jellyfin.photos),
                 blacklist: Blacklist {
                     media_types,
                     libraries,
                 },
+                spoilers: self.jellyfin.spoilers.unwrap_or_default(),
+                lyrics: self.jellyfin.lyrics.unwrap_or_default(),
+                extras: self.jellyfin.extras.unwrap_or_default(),
+                paused: self.jellyfin.paused.unwrap_or_default(),
+                idle: self.jellyfin.idle.unwrap_or_default(),
                 self_signed_cert: self.jellyfin.self_signed_cert.unwrap_or(false),
                 show_simple: self.jellyfin.show_simple.unwrap_or(false),
                 append_prefix: self.jellyfin.append_prefix.unwrap_or(false),
                 add_divider: self.jellyfin.add_divider.unwrap_or(false),
+                time_format: self.jellyfin.time_format,
+                syncplay_token: self.jellyfin.syncplay_token,
             },
             discord: Discord {
                 application_id,
                 buttons,
                 show_paused,
+                timestamp_tolerance,
+                timestamp_modes,
+                show_paused_duration,
+                pause_timeout,
+                show_pause_icon,
+                profiles,
+                application_switch_delay,
+                audio_book_chapter_timestamps,
+                queue_party_size,
+                syncplay_party_size,
+                syncplay_join,
+                syncplay_max_party_size,
                 image_text,
             },
             imgbb: ImgBB {
@@ -510,9 +759,18 @@ impl ConfigBuilder {
                 music_image,
                 audio_book_image,
                 book_image,
+                music_video_image,
+                video_image,
+                trailer_image,
+                recording_image,
+                tv_program_image,
+                photo_image,
+                browsing_image,
+                last_watched_image,
                 enable_images,
                 imgbb_images,
             },
+            schedule: self.schedule.unwrap_or_default(),
         }
     }
 }
@@ -1,14 +1,22 @@
 use clap::Parser;
 use colored::Colorize;
 use config::{get_config_path, get_urls_path, Config};
-use jellyfin_rpc::{Client, DisplayFormat, EpisodeDisplayOptions};
-use log::{debug, error, info};
+use schedule::Schedule;
+use jellyfin_rpc::{Client, DisplayFormat, EpisodeDisplayOptions, JfError};
+use log::{debug, error, info, warn};
 use retry::retry_with_index;
 use simple_logger::SimpleLogger;
 use std::{thread::sleep, time::Duration};
-use time::macros::format_description;
+use time::{
+    macros::format_description,
+    util::local_offset::{set_soundness, Soundness},
+    UtcOffset,
+};
 
 mod config;
+mod schedule;
+#[cfg(test)]
+mod tests;
 #[cfg(feature = "updates")]
 mod updates;
 
@@ -52,10 +60,18 @@ struct Args {
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
+    // Fallback for when the local offset can't be read later on
+    let local_offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+
     if std::env::var("RUST_LOG").is_err() {
-        std::env::set_var("RUST_LOG", args.log_level);
+        std::env::set_var("RUST_LOG", &args.log_level);
     }
 
+    // SAFETY: reading the local offset is only unsound while another thread modifies the
+    // environment, which nothing does after this point. Without this it can't be read again
+    // once the process is multi-threaded, so schedules wouldn't follow daylight saving time.
+    unsafe { set_soundness(Soundness::Unsound) };
+
     SimpleLogger::new()
         .with_level(log::LevelFilter::Info)
         .env()
@@ -72,16 +88,143 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let conf_path = &args
         .config
+        .clone()
         .unwrap_or(get_config_path().expect("default config path couldn't be determined"));
 
 
-    let conf = match Config::builder().load(conf_path, &args.jellyfin, &args.imgbb) {
+    let conf = load_config(conf_path, &args);
+    let schedule = conf.schedule.clone();
+
+    let mut clients = vec![build_client(conf, &args.image_urls)?];
+    // Index into `clients` for every schedule rule, `None` hides the presence
+    let mut profiles: Vec<Option<usize>> = Vec::new();
+
+    for rule in &schedule.rules {
+        if let Some(path) = &rule.config {
+            debug!("Loading schedule config {}", path);
+            let rule_conf = load_config(path, &args);
+
+            if rule_conf.schedule != Schedule::default() {
+                warn!("Ignoring the schedule in {}, only the main config can have one", path);
+            }
+
+            clients.push(build_client(rule_conf, &args.image_urls)?);
+            profiles.push(Some(clients.len() - 1));
+        } else {
+            profiles.push(None);
+        }
+    }
+
+    let mut current = 0;
+
+    connect(&mut clients[current]);
+
+    let mut currently_playing = String::new();
+
+    loop {
+        // Wait in one second steps so lyrics can follow the song between polls
+        for _ in 0..args.wait_time {
+            sleep(Duration::from_secs(1));
+
+            if currently_playing.is_empty() {
+                continue;
+            }
+
+            match clients[current].advance_lyrics() {
+                Ok(Some(update)) => {
+                    if let Some(snapshot) = update.snapshot() {
+                        currently_playing = snapshot.to_string();
+                        debug!("{}", currently_playing);
+                    }
+                }
+                Ok(None) => (),
+                Err(err) => debug!("Failed to advance lyrics: {}", err),
+            }
+        }
+
+        let target = match schedule.active_rule(local_offset) {
+            Some(rule) => profiles[rule],
+            None => Some(0),
+        };
+
+        let Some(target) = target else {
+            if !currently_playing.is_empty() {
+                let _ = clients[current].clear_activity();
+                info!("Schedule window started, cleared activity");
+                currently_playing.clear();
+            }
+            continue;
+        };
+
+        if target != current {
+            info!("Switching to schedule config");
+            let _ = clients[current].clear_activity();
+            let _ = clients[current].close();
+            current = target;
+            currently_playing.clear();
+            connect(&mut clients[current]);
+        }
+
+        let client = &mut clients[current];
+
+        match client.set_activity() {
+            Ok(update) => match update.snapshot() {
+                Some(snapshot) => {
+                    let activity = snapshot.to_string();
+
+                    if activity != currently_playing {
+                        currently_playing = activity;
+
+                        info!("{}", currently_playing);
+                    }
+                }
+                None if !currently_playing.is_empty() => {
+                    let _ = client.clear_activity();
+                    info!("Cleared activity");
+                    currently_playing.clear();
+                }
+                None => (),
+            },
+            Err(err @ JfError::Discord(_)) => {
+                error!("{}", err);
+                debug!("{:?}", err);
+                retry_with_index(
+                    retry::delay::Exponential::from_millis(1000),
+                    |current_try| {
+                        info!("Attempt {}: Trying to reconnect", current_try);
+                        match client.reconnect() {
+                            Ok(_) => retry::OperationResult::Ok(()),
+                            Err(err) => {
+                                error!("{}", err);
+                                retry::OperationResult::Retry(())
+                            }
+                        }
+                    },
+                )
+                .unwrap();
+                info!("Reconnected!");
+            }
+            Err(err) if err.is_transient() => {
+                warn!("{}", err);
+                debug!("{:?}", err);
+            }
+            Err(err) => {
+                error!("{}", err);
+                debug!("{:?}", err);
+            }
+        }
+    }
+}
+
+fn load_config(conf_path: &str, args: &Args) -> Config {
+    match Config::builder().load(conf_path, &args.jellyfin, &args.imgbb) {
         Ok(file) => file.build(),
         Err(error) => {
             error!("{}", "Failed to load configuration!".red().bold());
             match error {
-                config::ConfigBuilderLoaderError::InvalidConfig => {
+                config::ConfigBuilderLoaderError::InvalidConfig(err) => {
                     error!("{}", "The configuration file contains invalid syntax or values.".red().bold());
+                    error!("{}", err.to_string().red().bold());
                 },
                 config::ConfigBuilderLoaderError::InvalidConfigPath => {
                     error!("{}", format!(
@@ -92,23 +235,31 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 config::ConfigBuilderLoaderError::InvalidJellyfinKeyPath => {
                     error!("{}", format!(
                         "The Jellyfin API key path `{}` is invalid or inaccessible.",
-                        args.jellyfin.unwrap_or_default()
+                        args.jellyfin.clone().unwrap_or_default()
                     ).red().bold());
                 },
                 config::ConfigBuilderLoaderError::InvalidImgBBKeyPath => {
                     error!("{}", format!(
                         "The ImgBB API key path `{}` is invalid or inaccessible.",
-                        args.imgbb.unwrap_or_default()
+                        args.imgbb.clone().unwrap_or_default()
                     ).red().bold());
                 },
                 config::ConfigBuilderLoaderError::MissingJellyfinKey => {
                     error!("{}", "Jellyfin API key is required but was not provided.".red().bold());
                 },
+                config::ConfigBuilderLoaderError::MissingSyncPlayToken => {
+                    error!("{}", "syncplay_join requires jellyfin.syncplay_token but it was not provided.".red().bold());
+                },
             }
             std::process::exit(1)
         }
-    };
+    }
+}
 
+fn build_client(
+    conf: Config,
+    image_urls: &Option<String>,
+) -> Result<Client, Box<dyn std::error::Error>> {
     debug!("Creating jellyfin-rpc client builder");
     let mut builder = Client::builder();
 
@@ -121,16 +272,32 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .episode_divider(conf.jellyfin.add_divider)
         .episode_prefix(conf.jellyfin.append_prefix)
         .show_paused(conf.discord.show_paused)
+        .show_paused_duration(conf.discord.show_paused_duration)
+        .pause_timeout(conf.discord.pause_timeout)
+        .show_pause_icon(conf.discord.show_pause_icon)
+        .audio_book_chapter_timestamps(conf.discord.audio_book_chapter_timestamps)
+        .queue_party_size(conf.discord.queue_party_size)
+        .syncplay_party_size(conf.discord.syncplay_party_size)
+        .syncplay_join(conf.discord.syncplay_join)
+        .syncplay_max_party_size(conf.discord.syncplay_max_party_size)
         .episode_image(conf.images.episode_image)
         .movie_image(conf.images.movie_image)
         .tv_image(conf.images.tv_image)
         .music_image(conf.images.music_image)
         .audio_book_image(conf.images.audio_book_image)
         .book_image(conf.images.book_image)
+        .music_video_image(conf.images.music_video_image)
+        .video_image(conf.images.video_image)
+        .trailer_image(conf.images.trailer_image)
+        .recording_image(conf.images.recording_image)
+        .tv_program_image(conf.images.tv_program_image)
+        .photo_image(conf.images.photo_image)
+        .browsing_image(conf.images.browsing_image)
+        .last_watched_image(conf.images.last_watched_image)
         .show_images(conf.images.enable_images)
         .use_imgbb(conf.images.imgbb_images)
         .large_image_text(conf.discord.image_text)
-        .imgbb_urls_file_location(args.image_urls.unwrap_or(get_urls_path()?));
+        .imgbb_urls_file_location(image_urls.clone().unwrap_or(get_urls_path()?));
 
     if let Some(display) = conf.jellyfin.music.display {
         debug!("Found config.jellyfin.music.display");
@@ -169,6 +336,96 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         builder.episodes_separator(separator);
     }
 
+    if let Some(display) = conf.jellyfin.books.display {
+        debug!("Found config.jellyfin.books.display");
+        builder.books_display(display);
+    }
+
+    if let Some(separator) = conf.jellyfin.books.separator {
+        debug!("Found config.jellyfin.books.separator");
+        builder.books_separator(separator);
+    }
+
+    if let Some(display) = conf.jellyfin.tv.display {
+        debug!("Found config.jellyfin.tv.display");
+        builder.tv_display(display);
+    }
+
+    if let Some(separator) = conf.jellyfin.tv.separator {
+        debug!("Found config.jellyfin.tv.separator");
+        builder.tv_separator(separator);
+    }
+
+    if let Some(display) = conf.jellyfin.music_videos.display {
+        debug!("Found config.jellyfin.music_videos.display");
+        builder.music_videos_display(display);
+    }
+
+    if let Some(separator) = conf.jellyfin.music_videos.separator {
+        debug!("Found config.jellyfin.music_videos.separator");
+        builder.music_videos_separator(separator);
+    }
+
+    if let Some(display) = conf.jellyfin.audio_books.display {
+        debug!("Found config.jellyfin.audio_books.display");
+        builder.audio_books_display(display);
+    }
+
+    if let Some(separator) = conf.jellyfin.audio_books.separator {
+        debug!("Found config.jellyfin.audio_books.separator");
+        builder.audio_books_separator(separator);
+    }
+
+    if let Some(display) = conf.jellyfin.videos.display {
+        debug!("Found config.jellyfin.videos.display");
+        builder.videos_display(display);
+    }
+
+    if let Some(separator) = conf.jellyfin.videos.separator {
+        debug!("Found config.jellyfin.videos.separator");
+        builder.videos_separator(separator);
+    }
+
+    if let Some(display) = conf.jellyfin.trailers.display {
+        debug!("Found config.jellyfin.trailers.display");
+        builder.trailers_display(display);
+    }
+
+    if let Some(separator) = conf.jellyfin.trailers.separator {
+        debug!("Found config.jellyfin.trailers.separator");
+        builder.trailers_separator(separator);
+    }
+
+    if let Some(display) = conf.jellyfin.recordings.display {
+        debug!("Found config.jellyfin.recordings.display");
+        builder.recordings_display(display);
+    }
+
+    if let Some(separator) = conf.jellyfin.recordings.separator {
+        debug!("Found config.jellyfin.recordings.separator");
+        builder.recordings_separator(separator);
+    }
+
+    if let Some(display) = conf.jellyfin.tv_programs.display {
+        debug!("Found config.jellyfin.tv_programs.display");
+        builder.tv_programs_display(display);
+    }
+
+    if let Some(separator) = conf.jellyfin.tv_programs.separator {
+        debug!("Found config.jellyfin.tv_programs.separator");
+        builder.tv_programs_separator(separator);
+    }
+
+    if let Some(display) = conf.jellyfin.photos.display {
+        debug!("Found config.jellyfin.photos.display");
+        builder.photos_display(display);
+    }
+
+    if let Some(separator) = conf.jellyfin.photos.separator {
+        debug!("Found config.jellyfin.photos.separator");
+        builder.photos_separator(separator);
+    }
+
     if let Some(media_types) = conf.jellyfin.blacklist.media_types {
         debug!("Found config.jellyfin.blacklist.media_types");
         debug!("Blacklisted MediaTypes: {:?}", media_types);
@@ -181,11 +438,93 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         builder.blacklist_libraries(libraries);
     }
 
+    if let Some(series) = conf.jellyfin.spoilers.series {
+        debug!("Found config.jellyfin.spoilers.series");
+        builder.spoiler_series(series);
+    }
+
+    if let Some(libraries) = conf.jellyfin.spoilers.libraries {
+        debug!("Found config.jellyfin.spoilers.libraries");
+        builder.spoiler_libraries(libraries);
+    }
+
+    if let Some(mask) = conf.jellyfin.spoilers.mask {
+        debug!("Found config.jellyfin.spoilers.mask");
+        builder.spoiler_mask(mask);
+    }
+
+    builder
+        .spoiler_min_age_days(conf.jellyfin.spoilers.min_age_days)
+        .spoiler_hide_images(conf.jellyfin.spoilers.hide_images.unwrap_or(false));
+
     if let Some(application_id) = conf.discord.application_id {
         debug!("Found config.discord.application_id");
         builder.api_token(application_id);
     }
 
+    if let Some(format) = conf.jellyfin.lyrics.format {
+        debug!("Found config.jellyfin.lyrics.format");
+        builder.music_lyrics_format(format);
+    }
+
+    builder.music_lyrics(conf.jellyfin.lyrics.enabled.unwrap_or(false));
+
+    if let Some(extra_types) = conf.jellyfin.extras.ignored_types {
+        debug!("Found config.jellyfin.extras.ignored_types");
+        builder.ignored_extra_types(extra_types);
+    }
+
+    if let Some(display) = conf.jellyfin.idle.browsing_display {
+        debug!("Found config.jellyfin.idle.browsing_display");
+        builder.idle_browsing_display(display);
+    }
+
+    if let Some(display) = conf.jellyfin.idle.last_watched_display {
+        debug!("Found config.jellyfin.idle.last_watched_display");
+        builder.last_watched_display(display);
+    }
+
+    builder
+        .idle_browsing(conf.jellyfin.idle.browsing.unwrap_or(false))
+        .last_watched_minutes(conf.jellyfin.idle.last_watched_minutes)
+        .min_runtime(conf.jellyfin.extras.min_runtime.unwrap_or(0))
+        .min_play_time(conf.jellyfin.extras.min_play_time.unwrap_or(0));
+
+    if let Some(syncplay_token) = conf.jellyfin.syncplay_token {
+        debug!("Found config.jellyfin.syncplay_token");
+        builder.syncplay_token(syncplay_token);
+    }
+
+    if let Some(time_format) = conf.jellyfin.time_format {
+        debug!("Found config.jellyfin.time_format");
+        builder.time_format(time_format);
+    }
+
+    if let Some(tolerance) = conf.discord.timestamp_tolerance {
+        debug!("Found config.discord.timestamp_tolerance");
+        builder.timestamp_tolerance(tolerance);
+    }
+
+    for (media_type, mode) in conf.discord.timestamp_modes {
+        debug!("Found config.discord.timestamp_modes.{}", media_type);
+        builder.timestamp_mode(media_type, mode);
+    }
+
+    if let Some(delay) = conf.discord.application_switch_delay {
+        debug!("Found config.discord.application_switch_delay");
+        builder.application_switch_delay(delay);
+    }
+
+    for (media_type, profile) in conf.discord.profiles {
+        debug!("Found config.discord.profiles.{}", media_type);
+        builder.profile(media_type, profile);
+    }
+
+    for (media_type, display) in conf.jellyfin.paused {
+        debug!("Found config.jellyfin.paused.{}", media_type);
+        builder.paused_display(media_type, display);
+    }
+
     if let Some(buttons) = conf.discord.buttons {
         debug!("Found config.discord.buttons");
         builder.buttons(buttons);
@@ -212,8 +551,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     debug!("Building client");
-    let mut client = builder.build()?;
+    Ok(builder.build()?)
+}
 
+fn connect(client: &mut Client) {
     info!("Connecting to Discord");
     retry_with_index(
         retry::delay::Exponential::from_millis(1000),
@@ -230,49 +571,4 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     )
     .unwrap();
     info!("Connected!");
-
-    let mut currently_playing = String::new();
-
-    loop {
-        sleep(Duration::from_secs(args.wait_time as u64));
-
-        match client.set_activity() {
-            Ok(activity) => {
-                if activity.is_empty() && !currently_playing.is_empty() {
-                    let _ = client.clear_activity();
-                    info!("Cleared activity");
-                    currently_playing = activity;
-                } else if activity != currently_playing {
-                    currently_playing = activity;
-
-                    info!("{}", currently_playing);
-                }
-            }
-            Err(err) => {
-                // TODO: There has to be a better way to check this..
-                if err.to_string() == "content is blacklisted" {
-                    debug!("{}", err);
-                    continue;
-                }
-
-                error!("{}", err);
-                debug!("{:?}", err);
-                retry_with_index(
-                    retry::delay::Exponential::from_millis(1000),
-                    |current_try| {
-                        info!("Attempt {}: Trying to reconnect", current_try);
-                        match client.reconnect() {
-                            Ok(_) => retry::OperationResult::Ok(()),
-                            Err(err) => {
-                                error!("{}", err);
-                                retry::OperationResult::Retry(())
-                            }
-                        }
-                    },
-                )
-                .unwrap();
-                info!("Reconnected!");
-            }
-        }
-    }
 }
@@ -0,0 +1,123 @@
+use log::debug;
+use serde::{Deserialize, Serialize};
+use time::{OffsetDateTime, Time, UtcOffset, Weekday};
+
+time::serde::format_description!(hour_minute, Time, "[hour]:[minute]");
+time::serde::format_description!(utc_offset, UtcOffset, "[offset_hour]:[offset_minute]");
+
+/// Time windows during which the presence is hidden or another config is used.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct Schedule {
+    /// Offset used to evaluate the rules, `"+02:00"` for example.
+    ///
+    /// Defaults to the local offset of the system at launch.
+    #[serde(default, with = "utc_offset::option")]
+    pub utc_offset: Option<UtcOffset>,
+    /// Rules are checked in order, the first matching one wins.
+    #[serde(default)]
+    pub rules: Vec<ScheduleRule>,
+}
+
+/// A single time window.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScheduleRule {
+    /// Days the window starts on, every day if left empty.
+    pub days: Option<Vec<Day>>,
+    /// Start of the window, formatted as `"HH:MM"`.
+    #[serde(with = "hour_minute")]
+    pub start: Time,
+    /// End of the window, formatted as `"HH:MM"`.
+    ///
+    /// Windows ending before they start continue into the next day.
+    #[serde(with = "hour_minute")]
+    pub end: Time,
+    /// Path to a config file used during the window.
+    ///
+    /// The presence is hidden during the window if this is not set.
+    pub config: Option<String>,
+}
+
+/// Day of the week, accepts both full and three letter names.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Day {
+    #[serde(alias = "mon")]
+    Monday,
+    #[serde(alias = "tue")]
+    Tuesday,
+    #[serde(alias = "wed")]
+    Wednesday,
+    #[serde(alias = "thu")]
+    Thursday,
+    #[serde(alias = "fri")]
+    Friday,
+    #[serde(alias = "sat")]
+    Saturday,
+    #[serde(alias = "sun")]
+    Sunday,
+}
+
+impl From<Day> for Weekday {
+    fn from(value: Day) -> Self {
+        match value {
+            Day::Monday => Weekday::Monday,
+            Day::Tuesday => Weekday::Tuesday,
+            Day::Wednesday => Weekday::Wednesday,
+            Day::Thursday => Weekday::Thursday,
+            Day::Friday => Weekday::Friday,
+            Day::Saturday => Weekday::Saturday,
+            Day::Sunday => Weekday::Sunday,
+        }
+    }
+}
+
+impl Schedule {
+    /// Returns the index of the first rule whose window contains the current time.
+    ///
+    /// The local offset is used when no offset was set in the config, it is read on every call
+    /// so daylight saving time changes apply. `fallback_offset` is used if it can't be read.
+    pub fn active_rule(&self, fallback_offset: UtcOffset) -> Option<usize> {
+        let local_offset = UtcOffset::current_local_offset().unwrap_or(fallback_offset);
+
+        self.active_rule_at(OffsetDateTime::now_utc(), local_offset)
+    }
+
+    /// Same as [`Schedule::active_rule`] for a given point in time.
+    pub(crate) fn active_rule_at(
+        &self,
+        now: OffsetDateTime,
+        local_offset: UtcOffset,
+    ) -> Option<usize> {
+        let now = now.to_offset(self.utc_offset.unwrap_or(local_offset));
+
+        let index = self
+            .rules
+            .iter()
+            .position(|rule| rule.contains(now.weekday(), now.time()));
+
+        debug!("Active schedule rule: {:?}", index);
+
+        index
+    }
+}
+
+impl ScheduleRule {
+    pub(crate) fn contains(&self, weekday: Weekday, time: Time) -> bool {
+        if self.start <= self.end {
+            self.starts_on(weekday) && self.start <= time && time < self.end
+        } else {
+            // The window spans midnight, so the early morning part belongs to the previous day
+            (self.starts_on(weekday) && time >= self.start)
+                || (self.starts_on(weekday.previous()) && time < self.end)
+        }
+    }
+
+    fn starts_on(&self, weekday: Weekday) -> bool {
+        match &self.days {
+            Some(days) if !days.is_empty() => {
+                days.iter().any(|day| Weekday::from(*day) == weekday)
+            }
+            _ => true,
+        }
+    }
+}
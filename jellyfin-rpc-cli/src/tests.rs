@@ -0,0 +1,106 @@
+use crate::{
+    config::ConfigBuilder,
+    schedule::{Schedule, ScheduleRule},
+};
+use jellyfin_rpc::{MediaType, TimestampMode};
+use serde_json::json;
+use time::{macros::datetime, macros::time, UtcOffset, Weekday};
+
+/// Schedule with a single window from Friday 22:00 to 02:00 the next morning
+fn friday_night() -> Schedule {
+    serde_json::from_value(json!({
+        "utc_offset": "+02:00",
+        "rules": [{ "days": ["fri"], "start": "22:00", "end": "02:00" }],
+    }))
+    .unwrap()
+}
+
+#[test]
+fn schedule_midnight_wrap() {
+    let rule = &friday_night().rules[0];
+
+    assert!(!rule.contains(Weekday::Friday, time!(21:59)));
+    assert!(rule.contains(Weekday::Friday, time!(22:00)));
+    assert!(rule.contains(Weekday::Friday, time!(23:59)));
+    assert!(rule.contains(Weekday::Saturday, time!(00:00)));
+    assert!(rule.contains(Weekday::Saturday, time!(01:59)));
+    assert!(!rule.contains(Weekday::Saturday, time!(02:00)));
+    // The window only starts on fridays
+    assert!(!rule.contains(Weekday::Saturday, time!(22:30)));
+    assert!(!rule.contains(Weekday::Friday, time!(01:00)));
+}
+
+#[test]
+fn schedule_weekdays() {
+    let rule: ScheduleRule = serde_json::from_value(json!({
+        "days": ["sunday"],
+        "start": "08:00",
+        "end": "12:00",
+    }))
+    .unwrap();
+
+    assert!(rule.contains(Weekday::Sunday, time!(08:00)));
+    assert!(!rule.contains(Weekday::Sunday, time!(12:00)));
+    assert!(!rule.contains(Weekday::Monday, time!(09:00)));
+    assert!(!rule.contains(Weekday::Saturday, time!(09:00)));
+
+    let every_day: ScheduleRule = serde_json::from_value(json!({
+        "days": [],
+        "start": "08:00",
+        "end": "12:00",
+    }))
+    .unwrap();
+
+    assert!(every_day.contains(Weekday::Wednesday, time!(09:00)));
+}
+
+#[test]
+fn schedule_active_rule() {
+    let schedule = friday_night();
+
+    // 23:30 UTC on Friday is already 01:30 on Saturday at +02:00
+    assert_eq!(schedule.active_rule_at(datetime!(2024-01-05 23:30 UTC), UtcOffset::UTC), Some(0));
+    assert_eq!(schedule.active_rule_at(datetime!(2024-01-05 19:30 UTC), UtcOffset::UTC), None);
+
+    // The local offset is only used when the config doesn't set one
+    let schedule = Schedule {
+        utc_offset: None,
+        ..schedule
+    };
+    assert_eq!(schedule.active_rule_at(datetime!(2024-01-05 19:30 UTC), UtcOffset::UTC), None);
+    let local_offset = UtcOffset::from_hms(3, 0, 0).unwrap();
+    assert_eq!(schedule.active_rule_at(datetime!(2024-01-05 19:30 UTC), local_offset), Some(0));
+}
+
+/// Minimal config with `value` set at `section.key`
+fn config_with(
+    section: &str,
+    key: &str,
+    value: serde_json::Value,
+) -> serde_json::Result<ConfigBuilder> {
+    let mut config = json!({
+        "jellyfin": { "url": "http://localhost:8096", "username": "test" },
+    });
+    config[section][key] = value;
+
+    serde_json::from_value(config)
+}
+
+#[test]
+fn unknown_media_type_keys() {
+    let config =
+        config_with("discord", "timestamp_modes", json!({ "Movie": "remaining" })).unwrap();
+    assert_eq!(
+        config.discord.unwrap().timestamp_modes.unwrap()[&MediaType::Movie],
+        TimestampMode::Remaining
+    );
+
+    for (section, key, value) in [
+        ("discord", "timestamp_modes", json!("both")),
+        ("discord", "profiles", json!({})),
+        ("jellyfin", "paused", json!({ "details_text": "Paused" })),
+    ] {
+        let err = config_with(section, key, json!({ "moviez": value })).unwrap_err();
+        assert!(err.to_string().contains("unknown media type `moviez`"), "{}", err);
+    }
+}
@@ -1,4 +1,6 @@
-use std::{error::Error, fmt::Display};
+use reqwest::{header::InvalidHeaderValue, StatusCode};
+use std::{error::Error, fmt::Display, time::SystemTimeError};
+use time::error::InvalidFormatDescription;
 
 /// Error type
 #[derive(Debug)]
@@ -10,9 +12,63 @@ pub enum JfError {
     ContentBlacklist,
     MissingRequiredValues,
     NoImage,
+    /// Request to Jellyfin could not be completed
+    Http(reqwest::Error),
+    /// Jellyfin responded with an unsuccessful status code
+    JellyfinStatus {
+        status: StatusCode,
+        source: reqwest::Error,
+    },
+    /// Response or cache file could not be decoded
+    Json(serde_json::Error),
+    /// Communication with the Discord IPC socket failed
+    Discord(Box<dyn Error>),
+    /// Uploading or caching an image through an image host failed
+    ImageHost(Box<JfError>),
+    /// A URL in the options or from Jellyfin is invalid
+    Url(url::ParseError),
+    /// The API key can't be used as a header value
+    InvalidApiKey(InvalidHeaderValue),
+    /// The time format in the options is invalid
+    InvalidTimeFormat(InvalidFormatDescription),
+    Io(std::io::Error),
+    /// The SyncPlay token belongs to another user than the one being displayed
+    SyncPlayTokenUser,
+    SystemTime(SystemTimeError),
 }
 
-impl Error for JfError {}
+impl JfError {
+    /// Whether the error is likely to go away by itself on a later call,
+    /// such as a network hiccup or Discord restarting.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            JfError::Http(_) | JfError::Discord(_) | JfError::Io(_) => true,
+            JfError::JellyfinStatus { status, .. } => {
+                status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS
+            }
+            JfError::ImageHost(err) => err.is_transient(),
+            _ => false,
+        }
+    }
+}
+
+impl Error for JfError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            JfError::Http(err) => Some(err),
+            JfError::JellyfinStatus { source, .. } => Some(source),
+            JfError::Json(err) => Some(err),
+            JfError::Discord(err) => Some(err.as_ref()),
+            JfError::ImageHost(err) => Some(err.as_ref()),
+            JfError::Url(err) => Some(err),
+            JfError::InvalidApiKey(err) => Some(err),
+            JfError::InvalidTimeFormat(err) => Some(err),
+            JfError::Io(err) => Some(err),
+            JfError::SystemTime(err) => Some(err),
+            _ => None,
+        }
+    }
+}
 
 impl Display for JfError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -21,6 +77,65 @@ impl Display for JfError {
             JfError::UnrecognizedMediaType => write!(f, "unrecognized media type"),
             JfError::ContentBlacklist => write!(f, "content is blacklisted"),
             JfError::NoImage => write!(f, "media does not have an image"),
+            JfError::Http(err) => write!(f, "jellyfin request failed: {}", err),
+            JfError::JellyfinStatus { status, .. } => write!(f, "jellyfin responded with {}", status),
+            JfError::Json(err) => write!(f, "failed to decode json: {}", err),
+            JfError::Discord(err) => write!(f, "discord ipc error: {}", err),
+            JfError::ImageHost(err) => write!(f, "image host error: {}", err),
+            JfError::Url(err) => write!(f, "invalid url: {}", err),
+            JfError::InvalidApiKey(err) => write!(f, "invalid api key: {}", err),
+            JfError::InvalidTimeFormat(err) => write!(f, "invalid time format: {}", err),
+            JfError::Io(err) => write!(f, "io error: {}", err),
+            JfError::SyncPlayTokenUser => write!(f, "syncplay token belongs to another user"),
+            JfError::SystemTime(err) => write!(f, "system time error: {}", err),
+        }
+    }
+}
+
+impl From<reqwest::Error> for JfError {
+    fn from(value: reqwest::Error) -> Self {
+        match value.status() {
+            Some(status) => JfError::JellyfinStatus {
+                status,
+                source: value,
+            },
+            None => JfError::Http(value),
         }
     }
 }
+
+impl From<serde_json::Error> for JfError {
+    fn from(value: serde_json::Error) -> Self {
+        JfError::Json(value)
+    }
+}
+
+impl From<url::ParseError> for JfError {
+    fn from(value: url::ParseError) -> Self {
+        JfError::Url(value)
+    }
+}
+
+impl From<InvalidHeaderValue> for JfError {
+    fn from(value: InvalidHeaderValue) -> Self {
+        JfError::InvalidApiKey(value)
+    }
+}
+
+impl From<std::io::Error> for JfError {
+    fn from(value: std::io::Error) -> Self {
+        JfError::Io(value)
+    }
+}
+
+impl From<SystemTimeError> for JfError {
+    fn from(value: SystemTimeError) -> Self {
+        JfError::SystemTime(value)
+    }
+}
+
+impl From<InvalidFormatDescription> for JfError {
+    fn from(value: InvalidFormatDescription) -> Self {
+        JfError::InvalidTimeFormat(value)
+    }
+}
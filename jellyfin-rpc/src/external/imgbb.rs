@@ -44,7 +44,7 @@ pub struct ImageData {
     pub url: String,
 }
 
-pub fn get_image(client: &Client) -> JfResult<Url> {
+pub fn get_image(client: &Client, item_id: &str) -> JfResult<Url> {
     let mut image_urls = read_file(client)?;
     let system_time = SystemTime::now();
     let current_unix = system_time.duration_since(UNIX_EPOCH)?;
@@ -52,7 +52,7 @@ pub fn get_image(client: &Client) -> JfResult<Url> {
     if let Some((index, image_url)) = image_urls
         .iter()
         .enumerate()
-        .find(|(_, image_url)| client.session.as_ref().unwrap().item_id == image_url.id)
+        .find(|(_, image_url)| item_id == image_url.id)
     {
         let expiration_unix = image_url.expiration_as_duration();
 
@@ -65,11 +65,11 @@ pub fn get_image(client: &Client) -> JfResult<Url> {
         }
     }
 
-    let imgbb_url = upload(client)?;
+    let imgbb_url = upload(client, item_id)?;
     let imgbb_expiration = current_unix.as_secs() as usize + client.imgbb_options.expiration;
 
     let image_url = ImageUrl::new(
-        &client.session.as_ref().unwrap().item_id,
+        item_id,
         imgbb_url.as_str(),
         imgbb_expiration
     );
@@ -115,8 +115,8 @@ fn read_file(client: &Client) -> JfResult<Vec<ImageUrl>> {
     Ok(new)
 }
 
-fn upload(client: &Client) -> JfResult<Url> {
-    let image_bytes = client.reqwest.get(client.get_image()?).send()?.bytes()?;
+fn upload(client: &Client, item_id: &str) -> JfResult<Url> {
+    let image_bytes = client.reqwest.get(client.primary_image(item_id)?).send()?.bytes()?;
 
     let imgbb_client = reqwest::blocking::Client::builder().build()?;
 
@@ -124,11 +124,13 @@ fn upload(client: &Client) -> JfResult<Url> {
         .part("image", reqwest::blocking::multipart::Part::bytes(image_bytes.to_vec())
         .file_name("jellyfin"));
 
-    let res: ImgBBResponse = imgbb_client
+    let body = imgbb_client
         .post(format!("https://api.imgbb.com/1/upload?expiration={}&key={}", client.imgbb_options.expiration, client.imgbb_options.api_token))
         .multipart(form)
         .send()?
-        .json()?;
+        .text()?;
+
+    let res: ImgBBResponse = serde_json::from_str(&body)?;
 
     Ok(Url::parse(res.data.url.as_str())?)
 }
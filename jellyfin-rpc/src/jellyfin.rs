@@ -1,15 +1,35 @@
 use serde::{de::Visitor, Deserialize, Serialize};
-use std::time::{SystemTime, SystemTimeError, UNIX_EPOCH};
+use std::time::{Instant, SystemTime, SystemTimeError, UNIX_EPOCH};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+/// Jellyfin measures time in ticks of 100 nanoseconds.
+pub const TICKS_PER_SECOND: i64 = 10_000_000;
+pub const TICKS_PER_MILLISECOND: i64 = 10_000;
 
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "PascalCase")]
 pub struct RawSession {
+    pub user_id: Option<String>,
     pub user_name: Option<String>,
     pub now_playing_item: Option<NowPlayingItem>,
     pub play_state: Option<PlayState>,
+    pub device_name: Option<String>,
+    pub client: Option<String>,
+    pub transcoding_info: Option<TranscodingInfo>,
+    pub now_playing_queue: Option<Vec<QueueItem>>,
+    pub playlist_item_id: Option<String>,
+    pub last_activity_date: Option<String>,
 }
 
 impl RawSession {
+    /// Whether the client made a request to Jellyfin in the last `seconds`.
+    pub fn active_within(&self, seconds: i64) -> bool {
+        self.last_activity_date
+            .as_ref()
+            .and_then(|date| OffsetDateTime::parse(date, &Rfc3339).ok())
+            .is_some_and(|date| (OffsetDateTime::now_utc() - date).whole_seconds() <= seconds)
+    }
+
     pub fn build(self) -> Session {
         //TODO: Figure out how to avoid this clone
         let now_playing_item = self.now_playing_item.clone().unwrap();
@@ -21,9 +41,17 @@ impl RawSession {
         };
 
         Session {
+            program: now_playing_item.current_program,
             now_playing_item: self.now_playing_item.unwrap(),
             play_state: self.play_state.unwrap(),
             item_id: id.to_string(),
+            user_id: self.user_id,
+            device_name: self.device_name,
+            client: self.client,
+            transcoding_info: self.transcoding_info,
+            queue: self.now_playing_queue.unwrap_or_default(),
+            playlist_item_id: self.playlist_item_id,
+            fetched_at: Instant::now(),
         }
     }
 }
@@ -33,6 +61,18 @@ pub struct Session {
     pub now_playing_item: NowPlayingItem,
     pub play_state: PlayState,
     pub item_id: String,
+    pub user_id: Option<String>,
+    /// Program airing on the channel when watching [MediaType::LiveTv]
+    pub program: Option<Program>,
+    pub device_name: Option<String>,
+    pub client: Option<String>,
+    pub transcoding_info: Option<TranscodingInfo>,
+    /// Items queued on the client, including the current one
+    pub queue: Vec<QueueItem>,
+    /// Queue entry being played, tells duplicates in the queue apart
+    pub playlist_item_id: Option<String>,
+    /// When the session was received, used to advance the position between polls
+    pub fetched_at: Instant,
 }
 
 impl Session {
@@ -60,10 +100,194 @@ impl Session {
         artists
     }
 
-    pub fn get_time(&self) -> Result<PlayTime, SystemTimeError> {
+    /// Whole days elapsed since the item premiered, `None` if the premiere date is unknown.
+    pub fn days_since_premiere(&self) -> Option<i64> {
+        let premiere_date = self.now_playing_item.premiere_date.as_ref()?;
+        let premiere_date = OffsetDateTime::parse(premiere_date, &Rfc3339).ok()?;
+
+        Some((OffsetDateTime::now_utc() - premiere_date).whole_days())
+    }
+
+    /// Current playback position in seconds.
+    pub fn position_secs(&self) -> Option<i64> {
+        self.play_state.position_ticks.map(|ticks| ticks / TICKS_PER_SECOND)
+    }
+
+    /// Stream of the given type, such as `Audio` or `Video`.
+    ///
+    /// Prefers the audio track selected by the client, otherwise the first matching stream.
+    pub fn media_stream(&self, stream_type: &str) -> Option<&MediaStream> {
+        let streams = self.now_playing_item.media_streams.as_ref()?;
+
+        let selected = match stream_type {
+            "Audio" => self.play_state.audio_stream_index,
+            _ => None,
+        };
+
+        selected
+            .and_then(|index| {
+                streams
+                    .iter()
+                    .find(|stream| stream.stream_type == stream_type && stream.index == Some(index))
+            })
+            .or_else(|| streams.iter().find(|stream| stream.stream_type == stream_type))
+    }
+
+    /// Position of the current item in the queue, starting at 1, and the queue length.
+    pub fn queue_position(&self) -> Option<(usize, usize)> {
+        let index = self
+            .playlist_item_id
+            .as_ref()
+            .and_then(|playlist_item_id| {
+                self.queue
+                    .iter()
+                    .position(|item| item.playlist_item_id.as_ref() == Some(playlist_item_id))
+            })
+            .or_else(|| {
+                self.queue
+                    .iter()
+                    .position(|item| item.id == self.now_playing_item.id)
+            })?;
+
+        Some((index + 1, self.queue.len()))
+    }
+
+    /// Ids of the queued items in sorted order, used to recognize a queue after shuffling.
+    pub fn sorted_queue_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.queue.iter().map(|item| item.id.clone()).collect();
+        ids.sort();
+        ids
+    }
+
+    /// How the item is delivered to the client, such as "Direct Play" or "Transcode".
+    pub fn play_method(&self) -> Option<&'static str> {
+        match self.play_state.play_method.as_deref()? {
+            "DirectPlay" => Some("Direct Play"),
+            "DirectStream" => Some("Direct Stream"),
+            "Transcode" => Some("Transcode"),
+            _ => None,
+        }
+    }
+
+    /// Playback position in ticks, advanced by the time passed since the session was fetched.
+    pub fn live_position_ticks(&self) -> Option<i64> {
+        let position_ticks = self.play_state.position_ticks?;
+
+        if self.play_state.is_paused {
+            return Some(position_ticks);
+        }
+
+        let elapsed = self.fetched_at.elapsed().as_millis() as i64 * TICKS_PER_MILLISECOND;
+
+        Some(position_ticks + elapsed)
+    }
+
+    /// Runtime of the item in seconds.
+    pub fn runtime_secs(&self) -> Option<i64> {
+        self.now_playing_item.run_time_ticks.map(|ticks| ticks / TICKS_PER_SECOND)
+    }
+
+    /// Reading progress of a [MediaType::Book].
+    ///
+    /// Readers report their position as if it was a playback time in milliseconds.
+    /// Paged readers (PDF, comics) report the current page, while reflowable readers (EPUB)
+    /// report how far into the book they are in thousandths.
+    pub fn book_progress(&self) -> BookProgress {
+        let Some(position) = self
+            .play_state
+            .position_ticks
+            .map(|ticks| ticks / TICKS_PER_MILLISECOND)
+        else {
+            return BookProgress::default();
+        };
+
+        if self.is_reflowable_book() {
+            return BookProgress {
+                percent: Some((position / 10).clamp(0, 100)),
+                ..Default::default()
+            };
+        }
+
+        let page_count = self
+            .now_playing_item
+            .run_time_ticks
+            .map(|ticks| ticks / TICKS_PER_MILLISECOND)
+            .filter(|count| *count > 0);
+
+        BookProgress {
+            page: Some(position),
+            page_count,
+            percent: page_count.map(|count| (position * 100 / count).clamp(0, 100)),
+        }
+    }
+
+    fn is_reflowable_book(&self) -> bool {
+        let extension = self
+            .now_playing_item
+            .container
+            .clone()
+            .or_else(|| {
+                self.now_playing_item
+                    .path
+                    .as_ref()
+                    .and_then(|path| path.rsplit_once('.'))
+                    .map(|(_, extension)| extension.to_string())
+            })
+            .unwrap_or_default()
+            .to_lowercase();
+
+        matches!(extension.as_str(), "epub" | "mobi" | "azw3" | "fb2")
+    }
+
+    /// Index and chapter the current position falls into.
+    pub fn current_chapter(&self) -> Option<(usize, &Chapter)> {
+        let position_ticks = self.play_state.position_ticks?;
+
+        self.now_playing_item
+            .chapters
+            .as_ref()?
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, chapter)| chapter.start_position_ticks <= position_ticks)
+    }
+
+    /// Start and end of the current chapter in seconds from the start of the item.
+    pub fn chapter_bounds(&self) -> Option<(i64, i64)> {
+        let (index, chapter) = self.current_chapter()?;
+
+        let end_ticks = self
+            .now_playing_item
+            .chapters
+            .as_ref()?
+            .get(index + 1)
+            .map(|next| next.start_position_ticks)
+            .or(self.now_playing_item.run_time_ticks)?;
+
+        Some((
+            chapter.start_position_ticks / TICKS_PER_SECOND,
+            end_ticks / TICKS_PER_SECOND,
+        ))
+    }
+
+    /// Calculates the start and end timestamps of the playback.
+    ///
+    /// The timestamps stay anchored to `anchor` until the position deviates by more than
+    /// `tolerance` seconds (a seek) or playback is paused, to avoid jitter between polls.
+    pub fn get_time(
+        &self,
+        anchor: &mut Option<PlaybackAnchor>,
+        tolerance: i64,
+    ) -> Result<PlayTime, SystemTimeError> {
         match self.now_playing_item.media_type {
-            MediaType::Book => return Ok(PlayTime::None),
-            MediaType::LiveTv => return Ok(PlayTime::None),
+            MediaType::Book | MediaType::Photo => return Ok(PlayTime::None),
+            MediaType::LiveTv if !self.play_state.is_paused => {
+                return Ok(self
+                    .program
+                    .as_ref()
+                    .and_then(|program| Some(PlayTime::Some(program.start()?, program.end()?)))
+                    .unwrap_or(PlayTime::None))
+            }
             _ => {}
         }
 
@@ -71,28 +295,54 @@ impl Session {
             || self.play_state.position_ticks.is_none()
             || self.now_playing_item.run_time_ticks.is_none()
         {
+            // Resuming re-bases the timestamps
+            *anchor = None;
             return Ok(PlayTime::Paused);
         }
 
-        let ticks_to_seconds = 10000000;
+        let position_ticks = self.position_secs().expect("Unreachable error");
 
-        let position_ticks =
-            self.play_state.position_ticks.expect("Unreachable error") / ticks_to_seconds;
+        let runtime_ticks = self.runtime_secs().expect("Unreachable error");
 
-        let runtime_ticks = self
-            .now_playing_item
-            .run_time_ticks
-            .expect("Unreachable error")
-            / ticks_to_seconds;
+        let start = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64 - position_ticks;
+        let end = start + runtime_ticks;
 
-        Ok(PlayTime::Some(
-            SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64 - position_ticks,
-            SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64
-                + (runtime_ticks - position_ticks),
-        ))
+        match anchor {
+            Some(a)
+                if a.item_id == self.now_playing_item.id
+                    && a.end - a.start == runtime_ticks
+                    && (a.start - start).abs() <= tolerance =>
+            {
+                Ok(PlayTime::Some(a.start, a.end))
+            }
+            _ => {
+                *anchor = Some(PlaybackAnchor {
+                    item_id: self.now_playing_item.id.clone(),
+                    start,
+                    end,
+                });
+                Ok(PlayTime::Some(start, end))
+            }
+        }
     }
 }
 
+/// Reading progress of a book, every field is `None` when the reader doesn't report it.
+#[derive(Debug, Default, PartialEq)]
+pub struct BookProgress {
+    pub page: Option<i64>,
+    pub page_count: Option<i64>,
+    pub percent: Option<i64>,
+}
+
+/// Timestamps of the current playback, kept between polls by the client.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaybackAnchor {
+    item_id: String,
+    start: i64,
+    end: i64,
+}
+
 #[derive(PartialEq)]
 pub enum PlayTime {
     Some(i64, i64),
@@ -145,7 +395,8 @@ impl Button {
     }
 }
 
-#[derive(Deserialize, Debug, Clone)]
+/// The item currently being played, as returned by Jellyfin.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
 #[serde(rename_all = "PascalCase")]
 pub struct NowPlayingItem {
     // Generic
@@ -161,6 +412,19 @@ pub struct NowPlayingItem {
     pub community_rating: Option<f64>,
     pub original_title: Option<String>,
     pub path: Option<String>,
+    pub premiere_date: Option<String>,
+    pub container: Option<String>,
+    pub chapters: Option<Vec<Chapter>>,
+    pub media_streams: Option<Vec<MediaStream>>,
+    pub official_rating: Option<String>,
+    // Usually left out of sessions, filled in from the item details
+    pub people: Option<Vec<Person>>,
+    pub studios: Option<Vec<NameId>>,
+    pub taglines: Option<Vec<String>>,
+    pub tags: Option<Vec<String>>,
+    // Live TV related
+    pub channel_number: Option<String>,
+    pub current_program: Option<Program>,
     // Episode related
     pub parent_index_number: Option<i32>,
     pub index_number: Option<i32>,
@@ -173,17 +437,276 @@ pub struct NowPlayingItem {
     pub extra_type: Option<String>,
     pub album_id: Option<String>,
     pub album: Option<String>,
+    pub album_artist: Option<String>,
 }
 
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct UserData {
+    pub played: bool,
+    pub is_favorite: bool,
+    pub play_count: Option<i64>,
+    /// Episodes left to watch, only set for series and seasons
+    pub unplayed_item_count: Option<i64>,
+}
+
+/// Minimal representation of an item fetched through `Users/{id}/Items/{id}`.
+///
+/// Also used for the fields that sessions leave out, such as people and studios.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct Item {
+    pub user_data: Option<UserData>,
+    /// Number of episodes when the item is a series
+    pub recursive_item_count: Option<i64>,
+    pub people: Option<Vec<Person>>,
+    pub studios: Option<Vec<NameId>>,
+    pub taglines: Option<Vec<String>>,
+    pub tags: Option<Vec<String>>,
+}
+
+/// Name and id of an item, used for listings such as collections.
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "PascalCase")]
+pub struct ItemSummary {
+    pub id: String,
+    pub name: Option<String>,
+    /// Number of items in a folder or playlist, only set when requested through `Fields=ChildCount`
+    pub child_count: Option<usize>,
+}
+
+impl NowPlayingItem {
+    /// Fills the fields that the session left out with the fetched item details.
+    pub fn fill_details(&mut self, details: &Item) {
+        if self.people.is_none() {
+            self.people = details.people.clone();
+        }
+
+        if self.studios.is_none() {
+            self.studios = details.studios.clone();
+        }
+
+        if self.taglines.is_none() {
+            self.taglines = details.taglines.clone();
+        }
+
+        if self.tags.is_none() {
+            self.tags = details.tags.clone();
+        }
+    }
+
+    /// Names of the people with the given type, such as `Composer` or `Director`.
+    pub fn people_of_type(&self, person_type: &str) -> Vec<&str> {
+        self.people
+            .iter()
+            .flatten()
+            .filter(|person| person.person_type.as_deref() == Some(person_type))
+            .map(|person| person.name.as_str())
+            .collect()
+    }
+
+    /// Names of the studios, or labels for music.
+    pub fn studio_names(&self) -> Vec<&str> {
+        self.studios
+            .iter()
+            .flatten()
+            .map(|studio| studio.name.as_str())
+            .collect()
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Person {
+    pub name: String,
+    #[serde(rename = "Type")]
+    pub person_type: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct NameId {
+    pub name: String,
+}
+
+/// Common name of a video resolution, such as "4K" or "1080p".
+///
+/// The width is checked first since wide aspect ratios crop the height.
+pub fn resolution_name(width: i32, height: i32) -> String {
+    match width {
+        w if w >= 7000 => "8K".to_string(),
+        w if w >= 3200 => "4K".to_string(),
+        w if w >= 2400 => "1440p".to_string(),
+        w if w >= 1800 => "1080p".to_string(),
+        w if w >= 1200 => "720p".to_string(),
+        _ => format!("{}p", height),
+    }
+}
+
+/// A video, audio or subtitle stream of an item.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct MediaStream {
+    #[serde(rename = "Type")]
+    pub stream_type: String,
+    pub codec: Option<String>,
+    pub bit_rate: Option<i64>,
+    pub index: Option<i32>,
+    // Video related
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub video_range: Option<String>,
+    pub video_range_type: Option<String>,
+    // Audio related
+    pub channels: Option<i32>,
+    pub channel_layout: Option<String>,
+}
+
+impl MediaStream {
+    /// Common name of the video resolution, such as "4K" or "1080p".
+    pub fn resolution(&self) -> Option<String> {
+        Some(resolution_name(self.width?, self.height?))
+    }
+
+    /// Dynamic range of the video, `None` for SDR.
+    pub fn hdr(&self) -> Option<&'static str> {
+        let range_type = self.video_range_type.as_deref().unwrap_or_default();
+
+        match range_type {
+            t if t.starts_with("DOVI") => Some("DV"),
+            "HDR10Plus" => Some("HDR10+"),
+            "HDR10" => Some("HDR10"),
+            "HLG" => Some("HLG"),
+            "SDR" => None,
+            _ if self.video_range.as_deref() == Some("HDR") => Some("HDR"),
+            _ => None,
+        }
+    }
+
+    /// Channel layout of the audio, such as "Stereo" or "5.1".
+    pub fn audio_channels(&self) -> Option<String> {
+        if let Some(layout) = &self.channel_layout {
+            return Some(match layout.as_str() {
+                "mono" => "Mono".to_string(),
+                "stereo" => "Stereo".to_string(),
+                layout => layout.trim_end_matches("(side)").to_string(),
+            });
+        }
+
+        let name = match self.channels? {
+            1 => "Mono".to_string(),
+            2 => "Stereo".to_string(),
+            6 => "5.1".to_string(),
+            8 => "7.1".to_string(),
+            channels => format!("{}ch", channels),
+        };
+
+        Some(name)
+    }
+
+    /// Whether the stream uses a lossless audio codec.
+    pub fn is_lossless(&self) -> bool {
+        self.codec.as_ref().is_some_and(|codec| {
+            let codec = codec.to_lowercase();
+
+            matches!(
+                codec.as_str(),
+                "flac" | "alac" | "ape" | "wavpack" | "truehd" | "mlp" | "tta"
+            ) || codec.starts_with("pcm")
+                || codec.starts_with("dsd")
+        })
+    }
+}
+
+/// A Live TV program, from the session or `LiveTv/Programs`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Program {
+    pub name: String,
+    pub episode_title: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+}
+
+impl Program {
+    /// Unix timestamp of when the program starts.
+    pub fn start(&self) -> Option<i64> {
+        Self::parse_date(self.start_date.as_ref()?)
+    }
+
+    /// Unix timestamp of when the program ends.
+    pub fn end(&self) -> Option<i64> {
+        Self::parse_date(self.end_date.as_ref()?)
+    }
+
+    fn parse_date(date: &str) -> Option<i64> {
+        OffsetDateTime::parse(date, &Rfc3339)
+            .ok()
+            .map(|date| date.unix_timestamp())
+    }
+}
+
+/// Lyrics returned by `Audio/{id}/Lyrics`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Lyrics {
+    #[serde(default)]
+    pub lyrics: Vec<LyricLine>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct LyricLine {
+    pub text: String,
+    /// Start of the line in ticks, missing for unsynced lyrics.
+    pub start: Option<i64>,
+}
+
+impl Lyrics {
+    /// Whether the lines have timestamps, unsynced lyrics can't follow playback.
+    pub fn is_synced(&self) -> bool {
+        !self.lyrics.is_empty() && self.lyrics.iter().all(|line| line.start.is_some())
+    }
+
+    /// The line being sung at `position_ticks`, `None` before the first line or during empty lines.
+    pub fn line_at(&self, position_ticks: i64) -> Option<&str> {
+        if !self.is_synced() {
+            return None;
+        }
+
+        self.lyrics
+            .iter()
+            .take_while(|line| line.start.is_some_and(|start| start <= position_ticks))
+            .last()
+            .map(|line| line.text.trim())
+            .filter(|text| !text.is_empty())
+    }
+}
+
+/// Paged response used by endpoints such as `LiveTv/Programs`.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct Items<T> {
+    pub items: Vec<T>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Chapter {
+    pub name: Option<String>,
+    pub start_position_ticks: i64,
+}
+
+/// External link attached to an item, such as IMDb or MusicBrainz.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
 pub struct ExternalUrl {
     pub name: String,
     pub url: String,
 }
 
 /// The type of the currently playing content.
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
 pub enum MediaType {
     /// If the content playing is a Movie.
     Movie,
@@ -197,10 +720,40 @@ pub enum MediaType {
     Book,
     /// If the content playing is an Audio Book.
     AudioBook,
+    /// If the content playing is a Music Video.
+    MusicVideo,
+    /// If the content playing is a Video, such as home videos.
+    Video,
+    /// If the content playing is a Trailer.
+    Trailer,
+    /// If the content playing is a Live TV Recording.
+    Recording,
+    /// If the content playing is a TV Program.
+    TvProgram,
+    /// If the content playing is a Photo slideshow.
+    Photo,
     /// If the content is unrecognized.
+    #[default]
     None,
 }
 
+impl MediaType {
+    /// Whether the content is watched rather than listened to or read.
+    pub fn is_video(&self) -> bool {
+        matches!(
+            self,
+            MediaType::Movie
+                | MediaType::Episode
+                | MediaType::LiveTv
+                | MediaType::MusicVideo
+                | MediaType::Video
+                | MediaType::Trailer
+                | MediaType::Recording
+                | MediaType::TvProgram
+        )
+    }
+}
+
 impl Serialize for MediaType {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -212,8 +765,14 @@ impl Serialize for MediaType {
             MediaType::LiveTv => serializer.serialize_unit_variant("MediaType", 2, "LiveTv"),
             MediaType::Music => serializer.serialize_unit_variant("MediaType", 3, "Music"),
             MediaType::Book => serializer.serialize_unit_variant("MediaType", 4, "Book"),
-            MediaType::AudioBook => serializer.serialize_unit_variant("MediaType", 4, "AudioBook"),
-            MediaType::None => serializer.serialize_unit_variant("MediaType", 5, "None"),
+            MediaType::AudioBook => serializer.serialize_unit_variant("MediaType", 5, "AudioBook"),
+            MediaType::MusicVideo => serializer.serialize_unit_variant("MediaType", 6, "MusicVideo"),
+            MediaType::Video => serializer.serialize_unit_variant("MediaType", 7, "Video"),
+            MediaType::Trailer => serializer.serialize_unit_variant("MediaType", 8, "Trailer"),
+            MediaType::Recording => serializer.serialize_unit_variant("MediaType", 9, "Recording"),
+            MediaType::TvProgram => serializer.serialize_unit_variant("MediaType", 10, "TvProgram"),
+            MediaType::Photo => serializer.serialize_unit_variant("MediaType", 11, "Photo"),
+            MediaType::None => serializer.serialize_unit_variant("MediaType", 12, "None"),
         }
     }
 }
@@ -267,27 +826,33 @@ impl std::fmt::Display for MediaType {
             MediaType::Music => "Music",
             MediaType::Book => "Book",
             MediaType::AudioBook => "AudioBook",
+            MediaType::MusicVideo => "MusicVideo",
+            MediaType::Video => "Video",
+            MediaType::Trailer => "Trailer",
+            MediaType::Recording => "Recording",
+            MediaType::TvProgram => "TvProgram",
+            MediaType::Photo => "Photo",
             MediaType::None => "None",
         };
         write!(f, "{}", res)
     }
 }
 
-impl Default for MediaType {
-    fn default() -> Self {
-        Self::None
-    }
-}
-
 impl From<&'static str> for MediaType {
     fn from(value: &'static str) -> Self {
         match value {
             "episode" => Self::Episode,
             "movie" => Self::Movie,
             "music" | "audio" => Self::Music,
-            "livetv" | "tvchannel" => Self::LiveTv,
+            "livetv" | "tvchannel" | "livetvchannel" => Self::LiveTv,
             "book" => Self::Book,
             "audiobook" => Self::AudioBook,
+            "musicvideo" => Self::MusicVideo,
+            "video" => Self::Video,
+            "trailer" => Self::Trailer,
+            "recording" => Self::Recording,
+            "tvprogram" | "program" | "livetvprogram" => Self::TvProgram,
+            "photo" => Self::Photo,
             _ => Self::None,
         }
     }
@@ -299,9 +864,15 @@ impl From<String> for MediaType {
             "episode" => Self::Episode,
             "movie" => Self::Movie,
             "music" | "audio" => Self::Music,
-            "livetv" | "tvchannel" => Self::LiveTv,
+            "livetv" | "tvchannel" | "livetvchannel" => Self::LiveTv,
             "book" => Self::Book,
             "audiobook" => Self::AudioBook,
+            "musicvideo" => Self::MusicVideo,
+            "video" => Self::Video,
+            "trailer" => Self::Trailer,
+            "recording" => Self::Recording,
+            "tvprogram" | "program" | "livetvprogram" => Self::TvProgram,
+            "photo" => Self::Photo,
             _ => Self::None,
         }
     }
@@ -312,6 +883,42 @@ impl From<String> for MediaType {
 pub struct PlayState {
     pub is_paused: bool,
     pub position_ticks: Option<i64>,
+    pub play_method: Option<String>,
+    pub audio_stream_index: Option<i32>,
+}
+
+/// The user an access token belongs to, as returned by `Users/Me`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct User {
+    pub name: String,
+}
+
+/// A SyncPlay group as returned by `SyncPlay/List`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct SyncPlayGroup {
+    pub group_id: String,
+    pub group_name: String,
+    /// Usernames of the members
+    #[serde(default)]
+    pub participants: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct QueueItem {
+    pub id: String,
+    pub playlist_item_id: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct TranscodingInfo {
+    pub video_codec: Option<String>,
+    pub audio_codec: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -0,0 +1,258 @@
+use crate::{JfError, JfResult};
+use discord_rich_presence::DiscordIpc;
+use log::{debug, warn};
+use std::{
+    error::Error,
+    io::{self, ErrorKind, Read, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+#[cfg(windows)]
+use std::{fs::File, os::windows::io::AsRawHandle};
+
+/// How long a read waits before checking whether the listener was stopped
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Receives the secrets of join requests accepted in Discord.
+///
+/// Reading events blocks until Discord sends one, so they are read by a thread on a second
+/// connection, the connection used for the activity is never read from.
+pub(crate) struct JoinListener {
+    secrets: Receiver<String>,
+    thread: Option<JoinHandle<()>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl JoinListener {
+    /// Connects as the application and subscribes to `ACTIVITY_JOIN` once
+    pub(crate) fn start(application_id: &str) -> JfResult<Self> {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let mut ipc_client = JoinSocket {
+            client_id: application_id.to_string(),
+            socket: None,
+            stopped: stopped.clone(),
+        };
+
+        ipc_client.connect().map_err(JfError::Discord)?;
+        ipc_client
+            .send(
+                serde_json::json!({
+                    "cmd": "SUBSCRIBE",
+                    "evt": "ACTIVITY_JOIN",
+                    "args": {},
+                    "nonce": "jellyfin-rpc-join",
+                }),
+                1,
+            )
+            .map_err(JfError::Discord)?;
+
+        let (sender, secrets) = mpsc::channel();
+
+        let thread = thread::spawn(move || loop {
+            let payload = match ipc_client.recv() {
+                Ok((_, payload)) => payload,
+                Err(err) => {
+                    debug!("Stopped reading Discord events: {}", err);
+                    let _ = ipc_client.close();
+                    return;
+                }
+            };
+
+            if payload["evt"] == "ERROR" {
+                warn!("Discord refused the ACTIVITY_JOIN subscription: {}", payload["data"]);
+            } else if payload["cmd"] == "DISPATCH" && payload["evt"] == "ACTIVITY_JOIN" {
+                if let Some(secret) = payload["data"]["secret"].as_str() {
+                    debug!("Received a Discord join request");
+
+                    if sender.send(secret.to_string()).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            secrets,
+            thread: Some(thread),
+            stopped,
+        })
+    }
+
+    /// Secrets received since the last call, never blocks
+    pub(crate) fn secrets(&self) -> Vec<String> {
+        self.secrets.try_iter().collect()
+    }
+
+    /// Whether the connection to Discord is still open
+    pub(crate) fn is_running(&self) -> bool {
+        self.thread.as_ref().is_some_and(|thread| !thread.is_finished())
+    }
+}
+
+impl Drop for JoinListener {
+    fn drop(&mut self) {
+        // Reads give up within the poll interval, which closes the connection and with it
+        // the subscription, so a replaced listener can't act on join requests anymore
+        self.stopped.store(true, Ordering::Relaxed);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Discord IPC connection whose reads can be stopped from another thread,
+/// unlike [`discord_rich_presence::DiscordIpcClient`] which blocks until Discord sends something
+struct JoinSocket {
+    client_id: String,
+    socket: Option<Socket>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl JoinSocket {
+    fn socket(&mut self) -> io::Result<&mut Socket> {
+        self.socket.as_mut().ok_or(io::Error::new(
+            ErrorKind::NotConnected,
+            "Couldn't retrieve the Discord IPC socket",
+        ))
+    }
+}
+
+#[cfg(unix)]
+type Socket = UnixStream;
+#[cfg(windows)]
+type Socket = File;
+
+#[cfg(unix)]
+fn open_socket() -> io::Result<Socket> {
+    // Same places discord-rich-presence looks for the socket
+    let dir = ["XDG_RUNTIME_DIR", "TMPDIR", "TMP", "TEMP"]
+        .iter()
+        .find_map(|key| std::env::var(key).ok())
+        .unwrap_or_default();
+
+    for i in 0..10 {
+        for subpath in [
+            "",
+            "app/com.discordapp.Discord/",
+            "snap.discord-canary/",
+            "snap.discord/",
+        ] {
+            let path = std::path::Path::new(&dir)
+                .join(subpath)
+                .join(format!("discord-ipc-{}", i));
+
+            if let Ok(socket) = UnixStream::connect(path) {
+                socket.set_read_timeout(Some(POLL_INTERVAL))?;
+                return Ok(socket);
+            }
+        }
+    }
+
+    Err(io::Error::new(ErrorKind::NotFound, "Couldn't connect to the Discord IPC socket"))
+}
+
+#[cfg(windows)]
+fn open_socket() -> io::Result<Socket> {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    for i in 0..10 {
+        let path = format!(r"\\?\pipe\discord-ipc-{}", i);
+
+        if let Ok(socket) = std::fs::OpenOptions::new().access_mode(0x3).open(path) {
+            return Ok(socket);
+        }
+    }
+
+    Err(io::Error::new(ErrorKind::NotFound, "Couldn't connect to the Discord IPC socket"))
+}
+
+/// Reads what is available, times out after the poll interval like the unix socket does
+#[cfg(windows)]
+fn read_some(socket: &mut Socket, buffer: &mut [u8]) -> io::Result<usize> {
+    use windows_sys::Win32::{Foundation::HANDLE, System::Pipes::PeekNamedPipe};
+
+    let mut available = 0;
+    // SAFETY: the handle belongs to the open pipe and only the available byte count is written
+    let peeked = unsafe {
+        PeekNamedPipe(
+            socket.as_raw_handle() as HANDLE,
+            std::ptr::null_mut(),
+            0,
+            std::ptr::null_mut(),
+            &mut available,
+            std::ptr::null_mut(),
+        )
+    };
+
+    if peeked == 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    if available == 0 {
+        thread::sleep(POLL_INTERVAL);
+        return Err(ErrorKind::WouldBlock.into());
+    }
+
+    socket.read(buffer)
+}
+
+#[cfg(unix)]
+fn read_some(socket: &mut Socket, buffer: &mut [u8]) -> io::Result<usize> {
+    socket.read(buffer)
+}
+
+impl DiscordIpc for JoinSocket {
+    fn get_client_id(&self) -> &String {
+        &self.client_id
+    }
+
+    fn connect_ipc(&mut self) -> Result<(), Box<dyn Error>> {
+        self.socket = Some(open_socket()?);
+        Ok(())
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), Box<dyn Error>> {
+        self.socket()?.write_all(data)?;
+        Ok(())
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<(), Box<dyn Error>> {
+        let stopped = self.stopped.clone();
+        let socket = self.socket()?;
+        let mut filled = 0;
+
+        while filled < buffer.len() {
+            if stopped.load(Ordering::Relaxed) {
+                return Err("the join listener was stopped".into());
+            }
+
+            match read_some(socket, &mut buffer[filled..]) {
+                Ok(0) => return Err(io::Error::from(ErrorKind::UnexpectedEof).into()),
+                Ok(read) => filled += read,
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted
+                    ) => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), Box<dyn Error>> {
+        // Dropping the socket closes the connection
+        self.socket = None;
+        Ok(())
+    }
+}
@@ -1,40 +1,132 @@
-use discord_rich_presence::activity::{ActivityType, Button as ActButton};
-use discord_rich_presence::{
-    activity::{Activity, Assets, Timestamps},
-    DiscordIpc, DiscordIpcClient,
-};
+use discord_rich_presence::{DiscordIpc, DiscordIpcClient};
 pub use error::JfError;
-pub use jellyfin::{Button, MediaType};
-use jellyfin::{ExternalUrl, NowPlayingItem, PlayTime, RawSession, Session, VirtualFolder};
-use log::{debug, warn};
-use reqwest::header::{HeaderMap, AUTHORIZATION};
-use serde::{Deserialize, Serialize};
+pub use jellyfin::{Button, ExternalUrl, MediaType, NowPlayingItem};
+pub use presence::{
+    ActivityType, HiddenReason, PresenceSnapshot, PresenceUpdate, Profile, TimestampMode,
+};
+use ratelimit::TokenBucket;
+use jellyfin::{
+    BookProgress, Item, ItemSummary, Items, Lyrics, PlayTime, PlaybackAnchor, Program, RawSession,
+    Session, SyncPlayGroup, User, VirtualFolder,
+};
+use join::JoinListener;
+use log::{debug, info, warn};
+use reqwest::{
+    blocking::RequestBuilder,
+    header::{HeaderMap, AUTHORIZATION},
+    StatusCode,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::str::FromStr;
-use std::time::SystemTime;
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use time::{format_description::OwnedFormatItem, OffsetDateTime, UtcOffset};
 use url::Url;
 
 mod error;
 mod external;
 mod jellyfin;
+mod join;
+mod presence;
+mod ratelimit;
 #[cfg(test)]
 mod tests;
 
-pub(crate) type JfResult<T> = Result<T, Box<dyn std::error::Error>>;
+pub(crate) type JfResult<T> = Result<T, JfError>;
 
 pub const VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
 
 /// Client used to interact with jellyfin and discord
 pub struct Client {
     discord_ipc_client: DiscordIpcClient,
+    /// Default Discord Application ID, used when the profile doesn't set one
+    api_token: String,
+    /// Application ID the IPC client was created with
+    application_id: String,
+    /// Application ID a media type asked for and since when, swapped to once it stayed long enough
+    pending_application_id: Option<(String, Instant)>,
+    application_switch_delay: u64,
     url: Url,
     usernames: Vec<String>,
     reqwest: reqwest::blocking::Client,
     session: Option<Session>,
+    /// Last program fetched for a Live TV channel
+    live_program: Option<(String, Program)>,
+    /// Lyrics of the last music item, `None` inside if it has none
+    lyrics: Option<(String, Option<Lyrics>)>,
+    /// Details of the last item that sessions don't include, such as people and user data
+    item_details: Option<ItemDetails>,
+    /// Names of the collections each item belongs to, only loaded when `{collection}` is used
+    collections: Option<(HashMap<String, Vec<String>>, SystemTime)>,
+    /// Collections being fetched in the background, the previous ones are used meanwhile
+    collections_loading: Option<Receiver<HashMap<String, Vec<String>>>>,
+    /// Sorted ids of the last queue and the album or playlist it was started from
+    queue_source: Option<(Vec<String>, Option<String>)>,
+    queue_party_size: bool,
+    /// SyncPlay group the user is in, only looked up when it's displayed
+    syncplay_group: Option<SyncPlayGroup>,
+    /// Groups of the last `SyncPlay/List` request, refreshed every 30 seconds
+    syncplay_groups: Option<(Vec<SyncPlayGroup>, Instant)>,
+    /// User ids by lowercase username of everyone with a session, SyncPlay only lists names
+    session_users: HashMap<String, String>,
+    syncplay_party_size: bool,
+    syncplay_join: bool,
+    /// Access token of the Jellyfin client that joins SyncPlay groups
+    syncplay_token: Option<String>,
+    syncplay_max_party_size: Option<u32>,
+    /// Second Discord connection reading join requests, only used with `syncplay_join`
+    join_listener: Option<JoinListener>,
+    music_lyrics: bool,
+    music_lyrics_format: String,
+    ignored_extra_types: Vec<String>,
+    min_runtime: u64,
+    min_play_time: u64,
+    /// How long the playing item has played without pausing or seeking
+    play_timer: Option<PlayTimer>,
+    /// Session of a Jellyfin client that is open without playing anything
+    browsing_session: Option<RawSession>,
+    /// Last session that played and when it stopped
+    last_watched: Option<(Session, Instant)>,
+    /// Item of the last playing or paused update, only it can become the last watched item
+    shown_item: Option<String>,
+    idle_browsing: bool,
+    idle_browsing_display: DisplayFormat,
+    last_watched_minutes: Option<u64>,
+    last_watched_display: DisplayFormat,
+    last_update: Option<PresenceUpdate>,
+    last_sent: Option<PresenceSnapshot>,
+    rate_limiter: TokenBucket,
+    playback_anchor: Option<PlaybackAnchor>,
+    timestamp_tolerance: i64,
+    timestamp_modes: HashMap<MediaType, TimestampMode>,
+    show_paused_duration: bool,
+    pause_timeout: Option<u64>,
+    paused_displays: HashMap<MediaType, DisplayFormat>,
+    show_pause_icon: bool,
+    profiles: HashMap<MediaType, Profile>,
+    audio_book_chapter_timestamps: bool,
+    /// Item that is paused and the unix timestamp of when the pause was first seen
+    paused_since: Option<(String, i64)>,
+    utc_offset: Option<UtcOffset>,
+    /// Local offset when the client was built, used when it can't be read anymore
+    local_offset: UtcOffset,
+    time_format: OwnedFormatItem,
     buttons: Option<Vec<Button>>,
     music_display_options: DisplayOptions,
     movies_display_options: DisplayOptions,
     episodes_display_options: DisplayOptions,
+    books_display_options: DisplayOptions,
+    tv_display_options: DisplayOptions,
+    music_videos_display_options: DisplayOptions,
+    audio_books_display_options: DisplayOptions,
+    videos_display_options: DisplayOptions,
+    trailers_display_options: DisplayOptions,
+    recordings_display_options: DisplayOptions,
+    tv_programs_display_options: DisplayOptions,
+    photos_display_options: DisplayOptions,
     blacklist: Blacklist,
+    spoilers: SpoilerGuard,
     show_paused: bool,
     show_images: bool,
     imgbb_options: ImgBBOptions,
@@ -45,6 +137,14 @@ pub struct Client {
     default_music_image: String,
     default_book_image: String,
     default_audio_book_image: String,
+    default_music_video_image: String,
+    default_video_image: String,
+    default_trailer_image: String,
+    default_recording_image: String,
+    default_tv_program_image: String,
+    default_photo_image: String,
+    browsing_image: String,
+    last_watched_image: String,
     pause_icon_image: String,
 }
 
@@ -56,12 +156,23 @@ impl Client {
 
     /// Connects to the discord socket
     pub fn connect(&mut self) -> JfResult<()> {
-        self.discord_ipc_client.connect()
+        self.last_sent = None;
+        self.discord_ipc_client.connect().map_err(JfError::Discord)?;
+        self.start_join_listener();
+        Ok(())
+    }
+
+    /// Closes the connection to the discord socket
+    pub fn close(&mut self) -> JfResult<()> {
+        self.discord_ipc_client.close().map_err(JfError::Discord)
     }
 
     /// Reconnects to the discord socket
     pub fn reconnect(&mut self) -> JfResult<()> {
-        self.discord_ipc_client.reconnect()
+        self.last_sent = None;
+        self.discord_ipc_client.reconnect().map_err(JfError::Discord)?;
+        self.start_join_listener();
+        Ok(())
     }
 
     /// Clears current activity on discord if anything is being displayed
@@ -84,11 +195,20 @@ impl Client {
     /// client.clear_activity().unwrap();
     /// ```
     pub fn clear_activity(&mut self) -> JfResult<()> {
-        self.discord_ipc_client.clear_activity()
+        // Clearing can't wait for a token, but still counts towards the limit
+        self.rate_limiter.take();
+        self.last_sent = None;
+        self.last_update = None;
+        self.discord_ipc_client
+            .clear_activity()
+            .map_err(JfError::Discord)
     }
 
     /// Gathers information from jellyfin about what is being played and displays it according to the options supplied to the builder.
     ///
+    /// Identical activities are only sent once and updates are throttled to Discord's rate limit,
+    /// a throttled activity is sent by a later call once the limit allows it.
+    ///
     /// # Example
     /// ```no_run
     /// use jellyfin_rpc::Client;
@@ -104,153 +224,712 @@ impl Client {
     ///
     /// client.set_activity().unwrap();
     /// ```
-    pub fn set_activity(&mut self) -> JfResult<String> {
+    pub fn set_activity(&mut self) -> JfResult<PresenceUpdate> {
+        let update = self.build_presence()?;
+
+        if let Some(media_type) = update.snapshot().map(|snapshot| snapshot.media_type) {
+            // The current application keeps being used and switching is retried on the next call
+            if let Err(err) = self.switch_application(media_type) {
+                warn!("Failed to switch the Discord application: {}", err);
+            }
+        }
+
+        let update = self.publish(update)?;
+
+        if self.syncplay_join {
+            if self
+                .join_listener
+                .as_ref()
+                .is_none_or(|listener| !listener.is_running())
+            {
+                self.start_join_listener();
+            }
+
+            let secrets = self
+                .join_listener
+                .as_ref()
+                .map(JoinListener::secrets)
+                .unwrap_or_default();
+
+            for group_id in secrets {
+                self.join_syncplay_group(&group_id);
+            }
+        }
+
+        Ok(update)
+    }
+
+    /// Marks what changed since the last update and sends it to Discord if needed
+    fn publish(&mut self, mut update: PresenceUpdate) -> JfResult<PresenceUpdate> {
+        if let PresenceUpdate::Playing(snapshot)
+        | PresenceUpdate::Paused(snapshot)
+        | PresenceUpdate::Browsing(snapshot)
+        | PresenceUpdate::LastWatched(snapshot) = &mut update
+        {
+            snapshot.changed = !self
+                .last_update
+                .as_ref()
+                .and_then(|last| last.snapshot())
+                .is_some_and(|last| last.same_content(snapshot));
+
+            self.send_snapshot(snapshot, false)?;
+        }
+
+        self.last_update = Some(update.clone());
+
+        Ok(update)
+    }
+
+    /// Swaps the Discord application once a media type asked for another one for long enough,
+    /// so skipping through a queue of mixed media doesn't reconnect on every item
+    fn switch_application(&mut self, media_type: MediaType) -> JfResult<()> {
+        let Some(wanted) = self.pending_switch(media_type) else {
+            return Ok(());
+        };
+
+        info!("Switching the Discord application to {}", wanted);
+
+        // The old client stays in place until the new one is connected
+        let mut discord_ipc_client = DiscordIpcClient::new(&wanted).map_err(JfError::Discord)?;
+        discord_ipc_client.connect().map_err(JfError::Discord)?;
+
+        // The old activity would otherwise stay up until Discord notices the closed socket
+        let _ = self.discord_ipc_client.clear_activity();
+        let _ = self.discord_ipc_client.close();
+
+        self.discord_ipc_client = discord_ipc_client;
+        self.application_id = wanted;
+        self.pending_application_id = None;
+        self.last_sent = None;
+
+        self.start_join_listener();
+        Ok(())
+    }
+
+    /// Application ID the media type asked for, once it has done so for the switch delay
+    fn pending_switch(&mut self, media_type: MediaType) -> Option<String> {
+        let wanted = self
+            .profiles
+            .get(&media_type)
+            .and_then(|profile| profile.application_id.as_ref())
+            .unwrap_or(&self.api_token)
+            .clone();
+
+        if wanted == self.application_id {
+            self.pending_application_id = None;
+            return None;
+        }
+
+        if self
+            .pending_application_id
+            .as_ref()
+            .is_none_or(|(application_id, _)| application_id != &wanted)
+        {
+            self.pending_application_id = Some((wanted.clone(), Instant::now()));
+        }
+
+        let (_, since) = self.pending_application_id.as_ref().unwrap();
+
+        if since.elapsed().as_secs() < self.application_switch_delay {
+            debug!("Waiting before switching the Discord application to {}", wanted);
+            return None;
+        }
+
+        Some(wanted)
+    }
+
+    /// Listens for join requests with the current application, replacing the previous listener
+    fn start_join_listener(&mut self) {
+        // The previous listener is stopped first so it can't act on join requests meanwhile
+        self.join_listener = None;
+
+        if !self.syncplay_join {
+            return;
+        }
+
+        self.join_listener = JoinListener::start(&self.application_id)
+            .inspect_err(|err| warn!("Failed to listen for Discord join requests: {}", err))
+            .ok();
+    }
+
+    /// Joins the SyncPlay group from a Discord join secret
+    fn join_syncplay_group(&self, group_id: &str) {
+        if self
+            .syncplay_group
+            .as_ref()
+            .is_some_and(|group| group.group_id == group_id)
+        {
+            debug!("Already in the SyncPlay group");
+            return;
+        }
+
+        match self.post_syncplay_join(group_id) {
+            Ok(()) => info!("Joined SyncPlay group {}", group_id),
+            Err(err) => warn!("Failed to join SyncPlay group: {}", err),
+        }
+    }
+
+    /// Jellyfin adds the session that made the request to the group, so it's made with the
+    /// SyncPlay token after checking that it belongs to one of the configured users.
+    ///
+    /// Join requests are usually accepted while nothing plays, so the session can't be used.
+    fn post_syncplay_join(&self, group_id: &str) -> JfResult<()> {
+        let token = self
+            .syncplay_token
+            .as_ref()
+            .ok_or(JfError::MissingRequiredValues)?;
+        let with_token = |request: RequestBuilder| {
+            request
+                .header(AUTHORIZATION, format!("MediaBrowser Token=\"{}\"", token))
+                .header("X-Emby-Token", token)
+        };
+
+        let body = with_token(self.reqwest.get(self.url.join("Users/Me")?))
+            .send()?
+            .error_for_status()?
+            .text()?;
+        let user: User = serde_json::from_str(&body)?;
+
+        if self
+            .usernames
+            .iter()
+            .all(|username| username.to_lowercase() != user.name.to_lowercase())
+        {
+            return Err(JfError::SyncPlayTokenUser);
+        }
+
+        with_token(self.reqwest.post(self.url.join("SyncPlay/Join")?))
+            .json(&serde_json::json!({ "GroupId": group_id }))
+            .send()?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Moves the lyric line forward from the last session without asking Jellyfin,
+    /// meant to be called between calls to [`Client::set_activity`].
+    ///
+    /// Returns `None` if there is nothing to advance, such as when the music lyrics are disabled,
+    /// nothing is playing or the track doesn't have synced lyrics.
+    ///
+    /// A token is always left for [`Client::set_activity`] so lyrics can't delay other updates.
+    pub fn advance_lyrics(&mut self) -> JfResult<Option<PresenceUpdate>> {
+        if !self.music_lyrics || self.current_lyric().is_none() {
+            return Ok(None);
+        }
+
+        let Some(PresenceUpdate::Playing(snapshot)) = &self.last_update else {
+            return Ok(None);
+        };
+
+        // The last activity is kept while a new item plays for less than the minimum play time
+        if self
+            .session
+            .as_ref()
+            .is_none_or(|session| session.now_playing_item.id != snapshot.item.id)
+        {
+            return Ok(None);
+        }
+
+        let mut snapshot = snapshot.clone();
+        let state = self.render_state();
+
+        if snapshot.state == state {
+            return Ok(None);
+        }
+
+        snapshot.state = state;
+        snapshot.changed = true;
+
+        self.send_snapshot(&snapshot, true)?;
+
+        let update = PresenceUpdate::Playing(snapshot);
+        self.last_update = Some(update.clone());
+
+        Ok(Some(update))
+    }
+
+    /// Sends the snapshot unless it was already sent or the rate limit was reached,
+    /// low priority updates leave a token for the next regular update
+    fn send_snapshot(&mut self, snapshot: &PresenceSnapshot, low_priority: bool) -> JfResult<()> {
+        if self
+            .last_sent
+            .as_ref()
+            .is_some_and(|last| last.same_content(snapshot))
+        {
+            debug!("Activity is unchanged, not sending it to Discord");
+            return Ok(());
+        }
+
+        let allowed = if low_priority {
+            self.rate_limiter.try_take_spare(1)
+        } else {
+            self.rate_limiter.try_take()
+        };
+
+        if allowed {
+            self.discord_ipc_client
+                .set_activity(snapshot.activity())
+                .map_err(JfError::Discord)?;
+            self.last_sent = Some(snapshot.clone());
+        } else {
+            debug!("Activity update is rate limited, it will be sent on a later call");
+        }
+
+        Ok(())
+    }
+
+    /// Renders the current session without sending anything to Discord
+    fn build_presence(&mut self) -> JfResult<PresenceUpdate> {
         self.get_session()?;
 
-        // Make sure the blacklist cache is loaded/valid
+        // Make sure the library cache is loaded/valid
         match &self.blacklist.libraries {
-            BlacklistedLibraries::Uninitialized => {
-                self.reload_blacklist();
+            CachedLibraries::Uninitialized => {
+                self.reload_libraries();
             }
-            BlacklistedLibraries::Initialized(_, init_time) => {
+            CachedLibraries::Initialized(_, init_time) => {
                 if SystemTime::now()
                     .duration_since(*init_time)
                     .map(|passed| passed.as_secs() > 3600)
                     .unwrap_or(false)
                 {
-                    debug!("reloading blacklist after cache expiration");
-                    self.reload_blacklist();
+                    debug!("reloading libraries after cache expiration");
+                    self.reload_libraries();
                 }
             }
         }
 
-        if let Some(session) = &self.session {
-            if session.now_playing_item.media_type == MediaType::None {
-                return Err(Box::new(JfError::UnrecognizedMediaType));
-            }
+        self.render_presence()
+    }
 
-            if self.check_blacklist()? {
-                return Err(Box::new(JfError::ContentBlacklist));
-            }
+    /// Renders the presence of the session fetched by [`Client::get_session`]
+    fn render_presence(&mut self) -> JfResult<PresenceUpdate> {
+        let Some(session) = &self.session else {
+            return self.build_idle_presence();
+        };
 
-            let mut activity = Activity::new();
+        if session.now_playing_item.media_type == MediaType::None {
+            return Err(JfError::UnrecognizedMediaType);
+        }
 
-            let mut image_url;
+        if self.check_blacklist()? {
+            debug!("{}", JfError::ContentBlacklist);
+            return Ok(PresenceUpdate::Hidden(HiddenReason::Blacklisted));
+        }
 
-            match session.now_playing_item.media_type {
-                MediaType::Episode | MediaType::None => image_url = Url::from_str(&self.default_episode_image)?,
-                MediaType::Movie => image_url = Url::from_str(&self.default_movie_image)?,
-                MediaType::LiveTv => image_url = Url::from_str(&self.default_tv_image)?,
-                MediaType::Music => image_url = Url::from_str(&self.default_music_image)?,
-                MediaType::Book => image_url = Url::from_str(&self.default_book_image)?,
-                MediaType::AudioBook => image_url = Url::from_str(&self.default_audio_book_image)?,
-            }
+        if !self.played_long_enough() {
+            debug!("Item hasn't played for the minimum play time, keeping the last activity");
+            return Ok(match &self.last_update {
+                Some(update) if update.snapshot().is_some() => update.clone(),
+                _ => PresenceUpdate::Hidden(HiddenReason::MinPlayTime),
+            });
+        }
 
-            if self.imgbb_options.enabled && self.show_images {
-                if let Ok(imgbb_url) = external::imgbb::get_image(self) {
-                    image_url = imgbb_url;
-                } else {
-                    debug!("imgbb::get_image() didnt return an image, using default..")
-                }
-            } else if self.show_images {
-                if let Ok(iu) = self.get_image() {
-                    image_url = iu;
-                } else {
-                    debug!("self.get_image() didnt return an image, using default..")
-                }
-            }
+        self.update_item_details();
+        self.update_spoiler_guard();
+        self.update_live_program();
+        self.update_lyrics();
+        self.update_collections();
+        self.update_queue_source();
+        self.update_syncplay_group();
+
+        let session = self.session.as_ref().unwrap();
 
-            let mut assets = Assets::new().large_image(image_url.as_str());
+        let mut image_url = Url::from_str(self.default_image(session.now_playing_item.media_type))?;
 
-            let mut timestamps = Timestamps::new();
+        if let Some(default_image) = self
+            .profile()
+            .and_then(|profile| profile.default_image.as_ref())
+        {
+            image_url = Url::from_str(default_image)?;
+        }
 
-            match session.get_time()? {
-                PlayTime::Some(start, end) => timestamps = timestamps.start(start).end(end),
-                PlayTime::None => (),
-                PlayTime::Paused if self.show_paused => {
-                    assets = assets
-                        .small_image(&self.pause_icon_image)
-                        .small_text("Paused");
-                }
-                PlayTime::Paused => return Ok(String::new()),
+        image_url = self.item_image(image_url);
+
+        let play_time = session.get_time(&mut self.playback_anchor, self.timestamp_tolerance)?;
+
+        if play_time == PlayTime::Paused {
+            if self
+                .paused_since
+                .as_ref()
+                .is_none_or(|(item_id, _)| item_id != &session.now_playing_item.id)
+            {
+                self.paused_since = Some((
+                    session.now_playing_item.id.clone(),
+                    SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64,
+                ));
             }
+        } else {
+            self.paused_since = None;
+        }
 
-            let buttons: Vec<Button>;
-
-            if let Some(b) = self.get_buttons() {
-                // This gets around the value being dropped immediately at the end of this if statement
-                buttons = b;
-                activity = activity.buttons(
-                    buttons
-                        .iter()
-                        .map(|b| ActButton::new(&b.name, &b.url))
-                        .collect(),
-                );
+        let (start, end, paused) = match play_time {
+            PlayTime::Some(start, end) => {
+                let (start, end) = match session.chapter_bounds() {
+                    Some((chapter_start, chapter_end))
+                        if self.audio_book_chapter_timestamps
+                            && session.now_playing_item.media_type == MediaType::AudioBook =>
+                    {
+                        (start + chapter_start, start + chapter_end)
+                    }
+                    _ => (start, end),
+                };
+
+                let (start, end) = self
+                    .timestamp_modes
+                    .get(&session.now_playing_item.media_type)
+                    .copied()
+                    .unwrap_or_default()
+                    .apply(start, end);
+                (start, end, false)
             }
+            PlayTime::None => (None, None, false),
+            PlayTime::Paused if self.pause_timed_out() => {
+                return Ok(PresenceUpdate::Hidden(HiddenReason::PauseTimeout))
+            }
+            PlayTime::Paused if self.show_paused() => (
+                self.show_paused_duration
+                    .then(|| self.paused_since.as_ref().map(|(_, since)| *since))
+                    .flatten(),
+                None,
+                true,
+            ),
+            PlayTime::Paused => return Ok(PresenceUpdate::Hidden(HiddenReason::Paused)),
+        };
 
-            let mut state = self.get_state();
+        let paused_display = self
+            .paused_displays
+            .get(&session.now_playing_item.media_type)
+            .filter(|_| paused);
 
-            if state.len() > 128 {
-                state = state.chars().take(128).collect();
-            } else if state.len() < 3 {
-                // Add three zero width joiners due to discord requiring a minimum length of 3 chars in statuses
-                state += "‎‎‎";
-            }
+        let state = match paused_display.and_then(|display| display.state_text.as_ref()) {
+            Some(state_text) => Self::pad_text(self.parse_display(state_text)),
+            None => self.render_state(),
+        };
 
-            let mut details = self.get_details();
+        let mut details = match paused_display.and_then(|display| display.details_text.as_ref()) {
+            Some(details_text) => self.parse_display(details_text),
+            None => self.get_details(),
+        };
 
-            if details.len() > 128 {
-                details = details.chars().take(128).collect();
-            } else if details.len() < 3 {
-                // add three (3) zero width joiners
-                details += "‎‎‎";
-            }
+        if details.len() > 128 {
+            details = details.chars().take(128).collect();
+        } else if details.len() < 3 {
+            // add three (3) zero width joiners
+            details += "‎‎‎";
+        }
+
+        let mut image_text = match paused_display.and_then(|display| display.image_text.as_ref()) {
+            Some(image_text) => self.parse_display(image_text),
+            None => self.get_image_text(),
+        };
+
+        if image_text.is_empty() {
+            image_text = self.fallback_image_text().to_string();
+        }
+
+        if image_text.len() > 128 {
+            image_text = image_text.chars().take(128).collect();
+        } else if image_text.len() < 3 {
+            // add three zero width joiners
+            image_text += "‎‎‎";
+        }
+
+        let join_group = self
+            .syncplay_group
+            .as_ref()
+            .filter(|_| self.syncplay_join)
+            .map(|group| group.group_id.clone());
+
+        let snapshot = PresenceSnapshot {
+            media_type: session.now_playing_item.media_type,
+            activity_type: self.activity_type(session.now_playing_item.media_type),
+            item: session.now_playing_item.clone(),
+            details,
+            state,
+            image_url: image_url.to_string(),
+            image_text,
+            small_image: (paused && self.show_pause_icon).then(|| self.pause_icon_image.clone()),
+            small_text: (paused && self.show_pause_icon).then(|| "Paused".to_string()),
+            buttons: self.get_buttons().unwrap_or_default(),
+            start,
+            end,
+            party_size: self.party_size(),
+            // The group id is both the party and what friends need to join it
+            party_id: join_group.clone(),
+            join_secret: join_group,
+            changed: true,
+        };
+
+        self.shown_item = Some(session.now_playing_item.id.clone());
+
+        if paused {
+            Ok(PresenceUpdate::Paused(snapshot))
+        } else {
+            Ok(PresenceUpdate::Playing(snapshot))
+        }
+    }
+
+    /// Image shown for a media type when the item has none or images are disabled
+    fn default_image(&self, media_type: MediaType) -> &str {
+        match media_type {
+            MediaType::Episode | MediaType::None => &self.default_episode_image,
+            MediaType::Movie => &self.default_movie_image,
+            MediaType::LiveTv => &self.default_tv_image,
+            MediaType::Music => &self.default_music_image,
+            MediaType::Book => &self.default_book_image,
+            MediaType::AudioBook => &self.default_audio_book_image,
+            MediaType::MusicVideo => &self.default_music_video_image,
+            MediaType::Video => &self.default_video_image,
+            MediaType::Trailer => &self.default_trailer_image,
+            MediaType::Recording => &self.default_recording_image,
+            MediaType::TvProgram => &self.default_tv_program_image,
+            MediaType::Photo => &self.default_photo_image,
+        }
+    }
+
+    /// Profile of the playing media type
+    fn profile(&self) -> Option<&Profile> {
+        let session = self.session.as_ref()?;
+
+        self.profiles.get(&session.now_playing_item.media_type)
+    }
+
+    fn show_paused(&self) -> bool {
+        self.profile()
+            .and_then(|profile| profile.show_paused)
+            .unwrap_or(self.show_paused)
+    }
+
+    /// Image text used when the display doesn't render any
+    fn fallback_image_text(&self) -> &str {
+        self.profile()
+            .and_then(|profile| profile.image_text.as_deref())
+            .unwrap_or(&self.large_image_text)
+    }
+
+    /// What Discord shows the user to be doing for a media type
+    fn activity_type(&self, media_type: MediaType) -> Option<ActivityType> {
+        self.profiles
+            .get(&media_type)
+            .and_then(|profile| profile.activity_type)
+            .or(ActivityType::default_for(media_type))
+    }
 
-            let mut image_text = self.get_image_text();
+    /// Uses the image of the item over the default one when images are enabled
+    fn item_image(&self, mut image_url: Url) -> Url {
+        if !self.show_images {
+            return image_url;
+        }
+
+        let session = self.session.as_ref().unwrap();
 
-            if image_text.is_empty() {
-                image_text = self.large_image_text.clone();
+        // The series image is shown instead of the episode's while the spoiler guard is active
+        let item_id = if self.spoilers.active && self.spoilers.hide_images {
+            match session.now_playing_item.series_id.as_ref() {
+                Some(series_id) => series_id,
+                None => {
+                    debug!("Spoiler guard is active without a series, using default..");
+                    return image_url;
+                }
             }
+        } else {
+            &session.item_id
+        };
 
-            if image_text.len() > 128 {
-                image_text = image_text.chars().take(128).collect();
-            } else if image_text.len() < 3 {
-                // add three zero width joiners
-                image_text += "‎‎‎";
+        if self.imgbb_options.enabled {
+            match external::imgbb::get_image(self, item_id)
+                .map_err(|err| JfError::ImageHost(Box::new(err)))
+            {
+                Ok(imgbb_url) => image_url = imgbb_url,
+                Err(err) => debug!("imgbb::get_image() didnt return an image, using default.. ({})", err),
             }
+        } else if let Ok(iu) = self.primary_image(item_id) {
+            image_url = iu;
+        } else {
+            debug!("self.primary_image() didnt return an image, using default..")
+        }
 
-            assets = assets.large_text(image_text.as_str());
+        image_url
+    }
+
+    /// Shows the last watched item or the browsing activity when nothing is playing
+    fn build_idle_presence(&mut self) -> JfResult<PresenceUpdate> {
+        if let Some(minutes) = self.last_watched_minutes {
+            if self
+                .last_watched
+                .as_ref()
+                .is_some_and(|(_, stopped)| stopped.elapsed().as_secs() < minutes * 60)
+            {
+                // Rendering works on the current session, so the last one is put back meanwhile
+                let (session, stopped) = self.last_watched.take().unwrap();
+                self.session = Some(session);
+                let snapshot = self.build_last_watched();
+                self.last_watched = self.session.take().map(|session| (session, stopped));
 
-            match session.now_playing_item.media_type {
-                MediaType::Book => (),
-                MediaType::Music | MediaType::AudioBook => {
-                    activity = activity.activity_type(ActivityType::Listening)
+                if let Some(snapshot) = snapshot? {
+                    return Ok(PresenceUpdate::LastWatched(snapshot));
                 }
-                _ => activity = activity.activity_type(ActivityType::Watching),
             }
+        }
+
+        if !self.idle_browsing {
+            return Ok(PresenceUpdate::Idle);
+        }
+
+        let Some(browsing) = &self.browsing_session else {
+            return Ok(PresenceUpdate::Idle);
+        };
+
+        let parse = |input: &Option<String>| {
+            Self::sanitize_display_format(
+                &input
+                    .as_deref()
+                    .unwrap_or_default()
+                    .replace("{device}", browsing.device_name.as_deref().unwrap_or_default())
+                    .replace("{client}", browsing.client.as_deref().unwrap_or_default())
+                    .replace("{version}", VERSION.unwrap_or("UNKNOWN")),
+            )
+            .replace("{sep}", "-")
+        };
+
+        let mut image_text = parse(&self.idle_browsing_display.image_text);
+
+        if image_text.is_empty() {
+            image_text = self.fallback_image_text().to_string();
+        }
+
+        Ok(PresenceUpdate::Browsing(PresenceSnapshot {
+            media_type: MediaType::None,
+            activity_type: self.activity_type(MediaType::None),
+            item: NowPlayingItem::default(),
+            details: Self::pad_text(parse(&self.idle_browsing_display.details_text)),
+            state: Self::pad_text(parse(&self.idle_browsing_display.state_text)),
+            image_url: Url::from_str(&self.browsing_image)?.to_string(),
+            image_text: Self::pad_text(image_text),
+            small_image: None,
+            small_text: None,
+            buttons: Vec::new(),
+            start: None,
+            end: None,
+            party_size: None,
+            party_id: None,
+            join_secret: None,
+            changed: true,
+        }))
+    }
+
+    /// Renders the session put back from `last_watched`, `{__default}` is the regular details line
+    ///
+    /// Returns `None` if the item is blacklisted.
+    fn build_last_watched(&self) -> JfResult<Option<PresenceSnapshot>> {
+        let session = self.session.as_ref().unwrap();
+        let display = &self.last_watched_display;
+
+        if self.check_blacklist()? {
+            debug!("Last watched item is blacklisted, not showing it");
+            return Ok(None);
+        }
+
+        let parse = |input: &Option<String>| {
+            input
+                .as_ref()
+                .map(|text| self.parse_display(&text.replace("{__default}", &self.get_details())))
+                .unwrap_or_default()
+        };
+
+        let mut image_text = match &display.image_text {
+            Some(_) => parse(&display.image_text),
+            None => self.get_image_text(),
+        };
+
+        if image_text.is_empty() {
+            image_text = self.fallback_image_text().to_string();
+        }
+
+        Ok(Some(PresenceSnapshot {
+            media_type: session.now_playing_item.media_type,
+            activity_type: self.activity_type(session.now_playing_item.media_type),
+            item: session.now_playing_item.clone(),
+            details: Self::pad_text(parse(&display.details_text)),
+            state: Self::pad_text(parse(&display.state_text)),
+            image_url: self
+                .item_image(Url::from_str(&self.last_watched_image)?)
+                .to_string(),
+            image_text: Self::pad_text(image_text),
+            small_image: None,
+            small_text: None,
+            buttons: self.get_buttons().unwrap_or_default(),
+            start: None,
+            end: None,
+            party_size: None,
+            party_id: None,
+            join_secret: None,
+            changed: true,
+        }))
+    }
+
+    /// The SyncPlay group size takes priority over the queue position
+    fn party_size(&self) -> Option<[i32; 2]> {
+        let session = self.session.as_ref()?;
+
+        if let Some(group) = self
+            .syncplay_group
+            .as_ref()
+            .filter(|_| self.syncplay_party_size || self.syncplay_join)
+        {
+            let members = group.participants.len() as i32;
+            let max = self
+                .syncplay_max_party_size
+                .map(|max| (max as i32).max(members))
+                .unwrap_or(members);
 
-            activity = activity
-                .timestamps(timestamps)
-                .assets(assets)
-                .details(&details)
-                .state(&state);
+            return Some([members, max]);
+        }
+
+        self.queue_party_size
+            .then(|| session.queue_position())
+            .flatten()
+            .filter(|(_, length)| *length > 1)
+            .map(|(position, length)| [position as i32, length as i32])
+    }
 
-            self.discord_ipc_client.set_activity(activity)?;
+    fn render_state(&self) -> String {
+        Self::pad_text(self.get_state())
+    }
 
-            return Ok(format!("{} | {}", details, state));
+    fn pad_text(mut text: String) -> String {
+        if text.len() > 128 {
+            text = text.chars().take(128).collect();
+        } else if text.len() < 3 {
+            // Add three zero width joiners due to discord requiring a minimum length of 3 chars in statuses
+            text += "‎‎‎";
         }
-        Ok(String::new())
+
+        text
     }
 
     fn get_session(&mut self) -> JfResult<()> {
-        let sessions: Vec<RawSession> = self
-            .reqwest
-            .get(self.url.join("Sessions")?)
-            .send()?
-            .json()?;
+        let sessions: Vec<RawSession> = self.get_json("Sessions")?;
+        self.select_session(sessions);
+        Ok(())
+    }
+
+    /// Picks the first session of the users that plays something that isn't filtered out
+    fn select_session(&mut self, sessions: Vec<RawSession>) {
+        let previous = self.session.take();
+        self.browsing_session = None;
 
         debug!("Found {} sessions", sessions.len());
 
+        self.session_users = sessions
+            .iter()
+            .filter_map(|session| {
+                Some((session.user_name.as_ref()?.to_lowercase(), session.user_id.clone()?))
+            })
+            .collect();
+
         for session in sessions {
             debug!("Session username is {:?}", session.user_name);
             if let Some(username) = session.user_name.as_ref() {
@@ -263,6 +942,10 @@ impl Client {
                 }
 
                 if session.now_playing_item.is_none() {
+                    // Sessions of closed clients linger for a while, so only recent ones count
+                    if self.browsing_session.is_none() && session.active_within(300) {
+                        self.browsing_session = Some(session);
+                    }
                     continue;
                 }
                 debug!("NowPlayingItem exists");
@@ -274,22 +957,36 @@ impl Client {
 
                 let session = session.build();
 
+                if let Some(extra_type) = session.now_playing_item.extra_type.as_ref() {
+                    if self.ignored_extra_types.iter().any(|et| et == extra_type) {
+                        debug!(
+                            "Session is playing an ignored extra ({}), continuing loop",
+                            extra_type
+                        );
+                        continue;
+                    }
+                }
+
                 if session
-                    .now_playing_item
-                    .extra_type
-                    .as_ref()
-                    .is_some_and(|et| et == "ThemeSong")
+                    .runtime_secs()
+                    .is_some_and(|runtime| runtime < self.min_runtime as i64)
                 {
-                    debug!("Session is playing a theme song, continuing loop");
+                    debug!("Session is shorter than the minimum runtime, continuing loop");
                     continue;
                 }
 
                 self.session = Some(session);
-                return Ok(());
+                self.last_watched = None;
+                return;
             }
         }
-        self.session = None;
-        Ok(())
+
+        // Hidden items and ones that didn't play for the minimum play time were never shown
+        if let Some(previous) = previous.filter(|previous| {
+            self.shown_item.as_ref() == Some(&previous.now_playing_item.id)
+        }) {
+            self.last_watched = Some((previous, Instant::now()));
+        }
     }
 
     fn get_buttons(&self) -> Option<Vec<Button>> {
@@ -297,10 +994,15 @@ impl Client {
 
         let mut activity_buttons: Vec<Button> = Vec::new();
 
-        if let (Some(ext_urls), Some(buttons)) = (
-            &session.now_playing_item.external_urls,
-            self.buttons.as_ref(),
-        ) {
+        // Buttons of the media type's profile replace the global ones in every case
+        let buttons = self
+            .profile()
+            .and_then(|profile| profile.buttons.as_ref())
+            .or(self.buttons.as_ref());
+
+        if let (Some(ext_urls), Some(buttons)) =
+            (&session.now_playing_item.external_urls, buttons)
+        {
             let ext_urls: Vec<&ExternalUrl> = ext_urls
                 .iter()
                 .filter(|eu| {
@@ -327,7 +1029,7 @@ impl Client {
                 }
             }
             return Some(activity_buttons);
-        } else if let Some(buttons) = self.buttons.as_ref() {
+        } else if let Some(buttons) = buttons {
             for button in buttons {
                 if activity_buttons.len() == 2 {
                     break;
@@ -358,10 +1060,9 @@ impl Client {
         None
     }
 
-    fn get_image(&self) -> JfResult<Url> {
-        let session = self.session.as_ref().unwrap();
-
-        let path = "Items/".to_string() + &session.item_id + "/Images/Primary";
+    /// Primary image of an item, fails if the item doesn't have one
+    fn primary_image(&self, item_id: &str) -> JfResult<Url> {
+        let path = "Items/".to_string() + item_id + "/Images/Primary";
 
         let image_url = self.url.join(&path)?;
 
@@ -372,23 +1073,289 @@ impl Client {
             .text()?
             .contains("does not have an image of type Primary")
         {
-            Err(Box::new(JfError::NoImage))
+            Err(JfError::NoImage)
         } else {
             Ok(image_url)
         }
     }
 
-    fn sanitize_display_format(input: &str) -> String {
-        // Remove unnecessary spaces
-        let mut result = input.split_whitespace().collect::<Vec<&str>>().join(" ");
+    /// Offset of wall-clock placeholders, the local offset is read every time so it follows DST
+    fn utc_offset(&self) -> UtcOffset {
+        self.utc_offset
+            .unwrap_or_else(|| UtcOffset::current_local_offset().unwrap_or(self.local_offset))
+    }
 
-        // Remove duplicated separators
-        while result.contains("{sep}{sep}") || result.contains("{sep} {sep}") {
-            result = result.replace("{sep}{sep}", "{sep}");
-            result = result.replace("{sep} {sep}", "{sep}");
+    /// Seconds since the playing item was paused, `None` if it isn't paused
+    fn paused_for(&self) -> Option<i64> {
+        let session = self.session.as_ref()?;
+        let (item_id, since) = self.paused_since.as_ref()?;
+
+        if item_id != &session.now_playing_item.id {
+            return None;
         }
 
-        // Remove unnecessary separators
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
+
+        Some(now - since)
+    }
+
+    fn pause_timed_out(&self) -> bool {
+        self.pause_timeout
+            .is_some_and(|timeout| self.paused_for().is_some_and(|secs| secs >= timeout as i64))
+    }
+
+    /// Formats a number as `1st`, `2nd`, `3rd`, `4th` and so on
+    fn ordinal(number: i64) -> String {
+        let suffix = match (number % 10, number % 100) {
+            (_, 11..=13) => "th",
+            (1, _) => "st",
+            (2, _) => "nd",
+            (3, _) => "rd",
+            _ => "th",
+        };
+
+        format!("{}{}", number, suffix)
+    }
+
+    /// Formats seconds as `M:SS`, or `H:MM:SS` when longer than an hour
+    fn format_duration(seconds: i64) -> String {
+        let seconds = seconds.max(0);
+        let (hours, minutes, seconds) = (seconds / 3600, seconds % 3600 / 60, seconds % 60);
+
+        if hours > 0 {
+            format!("{}:{:02}:{:02}", hours, minutes, seconds)
+        } else {
+            format!("{}:{:02}", minutes, seconds)
+        }
+    }
+
+    /// Replaces the progress and chapter placeholders shared by every media type
+    fn parse_progress(&self, input: &str) -> String {
+        let session = self.session.as_ref().unwrap();
+
+        let (chapter, chapter_number) = session
+            .current_chapter()
+            .map(|(index, chapter)| {
+                (
+                    chapter.name.clone().unwrap_or_default(),
+                    (index + 1).to_string(),
+                )
+            })
+            .unwrap_or_default();
+        let chapter_count = session
+            .now_playing_item
+            .chapters
+            .as_ref()
+            .filter(|chapters| !chapters.is_empty())
+            .map(|chapters| chapters.len().to_string())
+            .unwrap_or_default();
+
+        let paused_for = self.paused_for().map(Self::format_duration).unwrap_or_default();
+
+        let input = input
+            .replace("{chapter}", &chapter)
+            .replace("{chapter-number}", &chapter_number)
+            .replace("{chapter-count}", &chapter_count)
+            .replace("{paused-for}", &paused_for);
+
+        let (position, runtime) = match (session.position_secs(), session.runtime_secs()) {
+            (Some(position), Some(runtime)) if runtime > 0 => (position.min(runtime), runtime),
+            (position, _) => {
+                return input
+                    .replace("{position}", &position.map(Self::format_duration).unwrap_or_default())
+                    .replace("{runtime}", "")
+                    .replace("{remaining}", "")
+                    .replace("{percent}", "")
+                    .replace("{progress-bar}", "")
+                    .replace("{ends-at}", "");
+            }
+        };
+
+        let remaining = runtime - position;
+        let percent = position * 100 / runtime;
+
+        let filled = (position * 10 / runtime) as usize;
+        let progress_bar = "▰".repeat(filled) + &"▱".repeat(10 - filled);
+
+        let ends_at = (OffsetDateTime::now_utc().to_offset(self.utc_offset())
+            + time::Duration::seconds(remaining))
+        .format(&self.time_format)
+        .unwrap_or_default();
+
+        input
+            .replace("{position}", &Self::format_duration(position))
+            .replace("{runtime}", &Self::format_duration(runtime))
+            .replace("{remaining}", &Self::format_duration(remaining))
+            .replace("{percent}", &format!("{}%", percent))
+            .replace("{progress-bar}", &progress_bar)
+            .replace("{ends-at}", &ends_at)
+    }
+
+    /// Replaces the placeholders describing how the item is played, the output of the
+    /// transcoder is used over the source when transcoding
+    fn parse_playback_info(&self, input: &str) -> String {
+        let session = self.session.as_ref().unwrap();
+        let transcoding = session.transcoding_info.as_ref();
+        let video_stream = session.media_stream("Video");
+        let audio_stream = session.media_stream("Audio");
+
+        let resolution = transcoding
+            .and_then(|t| Some(jellyfin::resolution_name(t.width?, t.height?)))
+            .or_else(|| video_stream.and_then(|stream| stream.resolution()))
+            .unwrap_or_default();
+        let hdr = video_stream
+            .and_then(|stream| stream.hdr())
+            .unwrap_or_default();
+        let video_codec = transcoding
+            .and_then(|t| t.video_codec.as_ref())
+            .or(video_stream.and_then(|stream| stream.codec.as_ref()))
+            .map(|c| c.to_uppercase())
+            .unwrap_or_default();
+        let audio_codec = transcoding
+            .and_then(|t| t.audio_codec.as_ref())
+            .or(audio_stream.and_then(|stream| stream.codec.as_ref()))
+            .map(|c| c.to_uppercase())
+            .unwrap_or_default();
+        let audio_channels = audio_stream
+            .and_then(|stream| stream.audio_channels())
+            .unwrap_or_default();
+        let play_method = session.play_method().unwrap_or_default();
+        let device = session.device_name.as_ref().unwrap_or(&"".to_string()).clone();
+        let client = session.client.as_ref().unwrap_or(&"".to_string()).clone();
+
+        input
+            .replace("{resolution}", &resolution)
+            .replace("{hdr}", hdr)
+            .replace("{video-codec}", &video_codec)
+            .replace("{audio-codec}", &audio_codec)
+            .replace("{audio-channels}", &audio_channels)
+            .replace("{play-method}", play_method)
+            .replace("{device}", &device)
+            .replace("{client}", &client)
+    }
+
+    /// Replaces the placeholders from the user's data, such as favorites and series progress
+    fn parse_user_data(&self, input: &str) -> String {
+        let details = self.item_details();
+        let user_data = details.and_then(|details| details.item.user_data.as_ref());
+
+        let favorite = if user_data.is_some_and(|ud| ud.is_favorite) {
+            "❤️"
+        } else {
+            ""
+        };
+        let play_count = user_data.and_then(|ud| ud.play_count);
+
+        let series = details.and_then(|details| details.series.as_ref());
+        let series_total = series
+            .and_then(|series| series.recursive_item_count)
+            .filter(|total| *total > 0);
+        let series_watched = series_total.and_then(|total| {
+            let unplayed = series?.user_data.as_ref()?.unplayed_item_count?;
+            Some((total - unplayed).clamp(0, total))
+        });
+
+        input
+            .replace("{favorite}", favorite)
+            .replace(
+                "{play-count}",
+                &play_count.map(|count| count.to_string()).unwrap_or_default(),
+            )
+            .replace(
+                "{play-count-ordinal}",
+                &play_count
+                    .filter(|count| *count > 0)
+                    .map(Self::ordinal)
+                    .unwrap_or_default(),
+            )
+            .replace(
+                "{series-watched}",
+                &series_watched.map(|w| w.to_string()).unwrap_or_default(),
+            )
+            .replace(
+                "{series-total}",
+                &series_total.map(|t| t.to_string()).unwrap_or_default(),
+            )
+            .replace(
+                "{series-percent}",
+                &series_watched
+                    .zip(series_total)
+                    .map(|(watched, total)| format!("{}%", watched * 100 / total))
+                    .unwrap_or_default(),
+            )
+    }
+
+    /// Replaces the placeholders describing who made a video and how it's rated
+    fn parse_video_metadata(&self, input: &str) -> String {
+        let session = self.session.as_ref().unwrap();
+        let item = &session.now_playing_item;
+
+        let director = item.people_of_type("Director").join(", ");
+        let mut studio = item.studio_names().join(", ");
+
+        if studio.is_empty() {
+            studio = item.series_studio.clone().unwrap_or_default();
+        }
+
+        let tagline = item
+            .taglines
+            .as_ref()
+            .and_then(|taglines| taglines.first())
+            .cloned()
+            .unwrap_or_default();
+        let rating = item.official_rating.clone().unwrap_or_default();
+        let collection = self.current_collections().join(", ");
+
+        let result = Self::replace_list(input, "cast", &item.people_of_type("Actor"));
+
+        result
+            .replace("{director}", &director)
+            .replace("{studio}", &studio)
+            .replace("{tagline}", &tagline)
+            .replace("{rating}", &rating)
+            .replace("{collection}", &collection)
+    }
+
+    /// Replaces `{name}` with every value and `{name|limit:N}` with the first `N` values
+    fn replace_list(input: &str, name: &str, values: &[&str]) -> String {
+        let mut result = input.replace(&format!("{{{}}}", name), &values.join(", "));
+        let prefix = format!("{{{}|limit:", name);
+
+        while let Some(start) = result.find(&prefix) {
+            let Some(len) = result[start..].find('}') else {
+                break;
+            };
+
+            let placeholder = result[start..=start + len].to_string();
+            let limit = placeholder[prefix.len()..placeholder.len() - 1]
+                .trim()
+                .parse::<usize>()
+                .unwrap_or(values.len());
+
+            let replacement = values
+                .iter()
+                .take(limit)
+                .copied()
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            result = result.replacen(&placeholder, &replacement, 1);
+        }
+
+        result
+    }
+
+    fn sanitize_display_format(input: &str) -> String {
+        // Remove unnecessary spaces
+        let mut result = input.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+        // Remove duplicated separators
+        while result.contains("{sep}{sep}") || result.contains("{sep} {sep}") {
+            result = result.replace("{sep}{sep}", "{sep}");
+            result = result.replace("{sep} {sep}", "{sep}");
+        }
+
+        // Remove unnecessary separators
         while result.starts_with("{sep}") {
             result = result
                 .drain(5..)
@@ -409,10 +1376,16 @@ impl Client {
     }
 
     fn parse_music_display(&self, input: &str) -> String {
-        let mut result = input.trim().to_string();
+        let mut result = self.parse_playback_info(&self.parse_progress(input.trim()));
+        result = self.parse_queue(&self.parse_user_data(&result));
         let session = self.session.as_ref().unwrap();
 
-        let separator = &self.music_display_options.separator;
+        // Music videos have a director and cast like other videos
+        if session.now_playing_item.media_type == MediaType::MusicVideo {
+            result = self.parse_video_metadata(&result);
+        }
+
+        let separator = &self.display_options().unwrap_or(&self.music_display_options).separator;
         let track = session.now_playing_item.name.as_ref();
         let artists = session.format_artists();
         let genres = session
@@ -432,10 +1405,58 @@ impl Client {
             .as_ref()
             .unwrap_or(&"".to_string())
             .clone();
+        let album_artist = session
+            .now_playing_item
+            .album_artist
+            .as_ref()
+            .unwrap_or(&"".to_string())
+            .clone();
+        let track_number = session
+            .now_playing_item
+            .index_number
+            .map(|n| n.to_string())
+            .unwrap_or_default();
+        let disc_number = session
+            .now_playing_item
+            .parent_index_number
+            .map(|n| n.to_string())
+            .unwrap_or_default();
+        let composer = session.now_playing_item.people_of_type("Composer").join(", ");
+        let label = session.now_playing_item.studio_names().join(", ");
+        let audio_stream = session.media_stream("Audio");
+        let bitrate = audio_stream
+            .and_then(|stream| stream.bit_rate)
+            .map(|b| format!("{} kbps", b / 1000))
+            .unwrap_or_default();
+        let container = session
+            .now_playing_item
+            .container
+            .as_ref()
+            .map(|c| c.to_uppercase())
+            .unwrap_or_default();
+        let codec = audio_stream
+            .and_then(|stream| stream.codec.as_ref())
+            .map(|c| c.to_uppercase())
+            .unwrap_or_default();
+        let lossless = if audio_stream.is_some_and(|stream| stream.is_lossless()) {
+            "Lossless"
+        } else {
+            ""
+        };
 
         result = result
+            .replace("{lyric}", self.current_lyric().unwrap_or_default())
             .replace("{track}", track)
+            .replace("{album-artist}", &album_artist)
             .replace("{album}", &album)
+            .replace("{track-number}", &track_number)
+            .replace("{disc-number}", &disc_number)
+            .replace("{composer}", &composer)
+            .replace("{label}", &label)
+            .replace("{bitrate}", &bitrate)
+            .replace("{container}", &container)
+            .replace("{codec}", &codec)
+            .replace("{lossless}", lossless)
             .replace("{artists}", &artists)
             .replace("{genres}", &genres)
             .replace("{year}", &year)
@@ -445,10 +1466,12 @@ impl Client {
     }
 
     fn parse_movies_display(&self, input: &str) -> String {
-        let mut result = input.trim().to_string();
+        let mut result = self.parse_progress(input.trim());
+        result = self.parse_video_metadata(&self.parse_playback_info(&result));
+        result = self.parse_queue(&self.parse_user_data(&result));
         let session = self.session.as_ref().unwrap();
 
-        let separator = &self.movies_display_options.separator;
+        let separator = &self.display_options().unwrap_or(&self.movies_display_options).separator;
         let title = session.now_playing_item.name.as_ref();
         let original_title = session
             .now_playing_item
@@ -481,7 +1504,7 @@ impl Client {
         result = result
             .replace("{title}", title)
             .replace("{original-title}", &original_title)
-            .replace("{genres}", &genres)
+            .replace("{genres}", genres)
             .replace("{year}", &year)
             .replace("{critic-score}", critic_score)
             .replace("{community-score}", community_score)
@@ -491,7 +1514,9 @@ impl Client {
     }
 
     fn parse_episodes_display(&self, input: &str) -> String {
-        let mut result = input.trim().to_string();
+        let mut result = self.parse_progress(input.trim());
+        result = self.parse_video_metadata(&self.parse_playback_info(&result));
+        result = self.parse_queue(&self.parse_user_data(&result));
         let session = self.session.as_ref().unwrap();
 
         let separator = &self.episodes_display_options.separator;
@@ -501,13 +1526,19 @@ impl Client {
             .as_ref()
             .unwrap_or(&"".to_string())
             .clone();
-        let episode_title = session.now_playing_item.name.as_ref();
-        let original_title = session
+        let mut episode_title = session.now_playing_item.name.clone();
+        let mut original_title = session
             .now_playing_item
             .original_title
             .as_ref()
             .unwrap_or(&"".to_string())
             .clone();
+
+        if self.spoilers.active {
+            episode_title = self.spoilers.mask.clone();
+            original_title = self.spoilers.mask.clone();
+        }
+
         let season = session.now_playing_item.parent_index_number.unwrap_or(0);
         let year = session
             .now_playing_item
@@ -520,12 +1551,6 @@ impl Client {
             .as_ref()
             .unwrap_or(&vec!["".to_string()])
             .join(", ");
-        let studio = session
-            .now_playing_item
-            .series_studio
-            .as_ref()
-            .unwrap_or(&"".to_string())
-            .clone();
 
         // One episode on Jellyfin can span across multiple actual episodes
         // For example E01-03 is 3 episodes in one media file
@@ -535,7 +1560,7 @@ impl Client {
         );
         result = result
             .replace("{show-title}", &show_title)
-            .replace("{title}", episode_title)
+            .replace("{title}", &episode_title)
             .replace("{original-title}", &original_title)
             .replace(
                 "{episode}",
@@ -555,226 +1580,802 @@ impl Client {
             .replace("{season-padded}", &format!("{:02}", season))
             .replace("{year}", &year)
             .replace("{genres}", &genres)
-            .replace("{studio}", &studio)
             .replace("{version}", VERSION.unwrap_or("UNKNOWN"));
 
-        Self::sanitize_display_format(&result).replace("{sep}", separator)
+        Self::sanitize_display_format(&result).replace("{sep}", separator)
+    }
+
+    fn parse_books_display(&self, input: &str) -> String {
+        let session = self.session.as_ref().unwrap();
+        let progress = session.book_progress();
+
+        let default = match progress {
+            BookProgress {
+                page: Some(page),
+                page_count: Some(page_count),
+                ..
+            } => format!("Page {} of {}", page, page_count),
+            BookProgress {
+                page: Some(page), ..
+            } => format!("Reading page {}", page),
+            BookProgress {
+                percent: Some(percent),
+                ..
+            } => format!("{}% read", percent),
+            _ => String::new(),
+        };
+
+        // {percent} has to be replaced before the progress placeholders since books have no runtime
+        let input = input
+            .replace("{__default}", &format!("{} {{sep}} ", default))
+            .replace(
+                "{percent}",
+                &progress.percent.map(|p| format!("{}%", p)).unwrap_or_default(),
+            );
+
+        let mut result = self.parse_progress(input.trim());
+
+        let separator = &self.books_display_options.separator;
+        let title = session.now_playing_item.name.as_ref();
+        let genres = session
+            .now_playing_item
+            .genres
+            .as_ref()
+            .unwrap_or(&vec!["".to_string()])
+            .join(", ");
+        let year = session
+            .now_playing_item
+            .production_year
+            .map(|y| y.to_string())
+            .unwrap_or_default();
+
+        result = result
+            .replace("{title}", title)
+            .replace("{authors}", &session.format_artists())
+            .replace("{genres}", &genres)
+            .replace("{year}", &year)
+            .replace(
+                "{page}",
+                &progress.page.map(|p| p.to_string()).unwrap_or_default(),
+            )
+            .replace(
+                "{page-count}",
+                &progress.page_count.map(|p| p.to_string()).unwrap_or_default(),
+            )
+            .replace("{version}", VERSION.unwrap_or("UNKNOWN"));
+
+        Self::sanitize_display_format(&result).replace("{sep}", separator)
+    }
+
+    fn parse_tv_display(&self, input: &str) -> String {
+        let session = self.session.as_ref().unwrap();
+        let program = session.program.as_ref();
+
+        let default = match program {
+            Some(_) => "{program} {sep} {episode-title} {sep} ",
+            None => "Live TV {sep} ",
+        };
+
+        let mut result = self.parse_progress(input.replace("{__default}", default).trim());
+
+        let format_time = |timestamp: Option<i64>| {
+            timestamp
+                .and_then(|t| OffsetDateTime::from_unix_timestamp(t).ok())
+                .and_then(|t| t.to_offset(self.utc_offset()).format(&self.time_format).ok())
+                .unwrap_or_default()
+        };
+
+        let separator = &self.tv_display_options.separator;
+        let channel = session.now_playing_item.name.as_ref();
+        let channel_number = session
+            .now_playing_item
+            .channel_number
+            .as_ref()
+            .unwrap_or(&"".to_string())
+            .clone();
+        let program_name = program.map(|p| p.name.clone()).unwrap_or_default();
+        let episode_title = program
+            .and_then(|p| p.episode_title.clone())
+            .unwrap_or_default();
+
+        result = result
+            .replace("{channel}", channel)
+            .replace("{channel-number}", &channel_number)
+            .replace("{program}", &program_name)
+            .replace("{episode-title}", &episode_title)
+            .replace(
+                "{program-start}",
+                &format_time(program.and_then(|p| p.start())),
+            )
+            .replace("{program-end}", &format_time(program.and_then(|p| p.end())))
+            .replace("{version}", VERSION.unwrap_or("UNKNOWN"));
+
+        Self::sanitize_display_format(&result).replace("{sep}", separator)
+    }
+
+    /// Parses a template with the placeholders of the playing media type
+    fn parse_display(&self, input: &str) -> String {
+        let session = self.session.as_ref().unwrap();
+
+        match session.now_playing_item.media_type {
+            MediaType::Music | MediaType::MusicVideo | MediaType::AudioBook => {
+                self.parse_music_display(input)
+            }
+            MediaType::Episode => self.parse_episodes_display(input),
+            MediaType::LiveTv => self.parse_tv_display(input),
+            MediaType::Book => self.parse_books_display(input),
+            _ => self.parse_movies_display(input),
+        }
+    }
+
+    /// Display options of the playing media type, `None` for types without any
+    fn display_options(&self) -> Option<&DisplayOptions> {
+        let session = self.session.as_ref().unwrap();
+
+        match session.now_playing_item.media_type {
+            MediaType::Music => Some(&self.music_display_options),
+            MediaType::Movie => Some(&self.movies_display_options),
+            MediaType::Episode => Some(&self.episodes_display_options),
+            MediaType::Book => Some(&self.books_display_options),
+            MediaType::LiveTv => Some(&self.tv_display_options),
+            MediaType::MusicVideo => Some(&self.music_videos_display_options),
+            MediaType::AudioBook => Some(&self.audio_books_display_options),
+            MediaType::Video => Some(&self.videos_display_options),
+            MediaType::Trailer => Some(&self.trailers_display_options),
+            MediaType::Recording => Some(&self.recordings_display_options),
+            MediaType::TvProgram => Some(&self.tv_programs_display_options),
+            MediaType::Photo => Some(&self.photos_display_options),
+            MediaType::None => None,
+        }
+    }
+
+    /// What `{__default}` stands for in the details and state of the playing item,
+    /// books and Live TV fill in their state default while parsing since it depends on progress
+    fn default_templates(&self) -> (&'static str, &'static str) {
+        let session = self.session.as_ref().unwrap();
+
+        match session.now_playing_item.media_type {
+            MediaType::Music | MediaType::MusicVideo => ("{track}", "By {artists} {sep} "),
+            // Audiobooks without an album are a single file named after the book
+            MediaType::AudioBook => (
+                if session.now_playing_item.album.is_some() {
+                    "{album}"
+                } else {
+                    "{track}"
+                },
+                if session.format_artists().is_empty() {
+                    ""
+                } else {
+                    "By {artists} {sep} "
+                },
+            ),
+            MediaType::Episode => ("{show-title}", ""),
+            MediaType::LiveTv => ("{channel}", "{__default}"),
+            MediaType::Book => ("{title}", "{__default}"),
+            _ => ("{title}", ""),
+        }
+    }
+
+    fn get_details(&self) -> String {
+        let session = self.session.as_ref().unwrap();
+
+        let Some(display_options) = self.display_options() else {
+            return session.now_playing_item.name.to_string();
+        };
+
+        let (default_details, _) = self.default_templates();
+        let display_details_format = display_options.display.details_text.as_ref().unwrap();
+
+        self.parse_display(&display_details_format.replace("{__default}", default_details))
+    }
+
+    fn get_state(&self) -> String {
+        let session = self.session.as_ref().unwrap();
+        let media_type = session.now_playing_item.media_type;
+
+        if media_type == MediaType::Music && self.music_lyrics && self.current_lyric().is_some() {
+            return self.parse_music_display(&self.music_lyrics_format);
+        }
+
+        let Some(display_options) = self.display_options() else {
+            return session
+                .now_playing_item
+                .genres
+                .as_ref()
+                .unwrap_or(&vec!["".to_string()])
+                .join(", ");
+        };
+
+        let (_, default_state) = self.default_templates();
+        let display_state_format = display_options.display.state_text.as_ref().unwrap();
+
+        self.parse_display(&display_state_format.replace("{__default}", default_state))
+    }
+
+    fn get_image_text(&self) -> String {
+        self.display_options()
+            .and_then(|display_options| display_options.display.image_text.as_ref())
+            .map(|display_image_format| self.parse_display(display_image_format))
+            .unwrap_or_default()
+    }
+
+    fn check_blacklist(&self) -> JfResult<bool> {
+        let session = self.session.as_ref().unwrap();
+
+        if self
+            .blacklist
+            .media_types
+            .iter()
+            .any(|m| m == &session.now_playing_item.media_type)
+        {
+            return Ok(true);
+        }
+
+        if self.blacklist.check_item(&session.now_playing_item) {
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    /// Decide whether the spoiler guard applies to the current session
+    fn update_spoiler_guard(&mut self) {
+        self.spoilers.active = false;
+
+        let session = self.session.as_ref().unwrap();
+
+        if session.now_playing_item.media_type != MediaType::Episode {
+            return;
+        }
+
+        let series_guarded = session
+            .now_playing_item
+            .series_name
+            .as_ref()
+            .is_some_and(|name| {
+                self.spoilers
+                    .series
+                    .iter()
+                    .any(|s| s.to_lowercase() == name.to_lowercase())
+            });
+
+        if !series_guarded
+            && !self
+                .spoilers
+                .libraries
+                .check_path(session.now_playing_item.path.as_ref().unwrap_or(&String::new()))
+        {
+            return;
+        }
+
+        if let (Some(min_age), Some(age)) =
+            (self.spoilers.min_age_days, session.days_since_premiere())
+        {
+            if age >= min_age as i64 {
+                debug!("Episode premiered {} days ago, not hiding spoilers", age);
+                return;
+            }
+        }
+
+        if self.series_finished() {
+            debug!("Series is marked as played, not hiding spoilers");
+            return;
+        }
+
+        debug!("Spoiler guard is active");
+        self.spoilers.active = true;
+    }
+
+    /// Find the program airing on the current Live TV channel if the session doesn't include it
+    fn update_live_program(&mut self) {
+        let session = self.session.as_ref().unwrap();
+
+        if session.now_playing_item.media_type != MediaType::LiveTv || session.program.is_some() {
+            return;
+        }
+
+        let channel_id = session.now_playing_item.id.clone();
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+
+        let cached = self.live_program.as_ref().filter(|(cached_id, program)| {
+            cached_id == &channel_id && program.end().is_some_and(|end| end > now)
+        });
+
+        let program = match cached {
+            Some((_, program)) => Some(program.clone()),
+            None => {
+                let mut path = format!("LiveTv/Programs?ChannelIds={}&IsAiring=true&Limit=1", channel_id);
+
+                if let Some(user_id) = &session.user_id {
+                    path += &format!("&UserId={}", user_id);
+                }
+
+                match self.get_json::<Items<Program>>(&path) {
+                    Ok(programs) => programs.items.into_iter().next(),
+                    Err(err) => {
+                        warn!("Failed to fetch the current program: {}", err);
+                        None
+                    }
+                }
+            }
+        };
+
+        self.live_program = program.clone().map(|program| (channel_id, program));
+        self.session.as_mut().unwrap().program = program;
+    }
+
+    /// Whether the playing item has played for at least the minimum play time,
+    /// paused time doesn't count and seeking starts over
+    fn played_long_enough(&mut self) -> bool {
+        let session = self.session.as_ref().unwrap();
+        let item_id = &session.now_playing_item.id;
+        let paused = session.play_state.is_paused;
+        let position = session.position_secs();
+        let now = Instant::now();
+
+        match &mut self.play_timer {
+            Some(timer) if &timer.item_id == item_id => {
+                let elapsed = now.duration_since(timer.last_seen);
+
+                // Only time between two polls that both saw the item playing counts
+                if !paused && !timer.paused {
+                    let seeked = timer.position.zip(position).is_some_and(|(before, after)| {
+                        (after - before - elapsed.as_secs() as i64).abs() > self.timestamp_tolerance
+                    });
+
+                    if seeked {
+                        debug!("Playback position jumped, restarting the minimum play time");
+                        timer.played = Duration::ZERO;
+                    } else {
+                        timer.played += elapsed;
+                    }
+                }
+
+                timer.last_seen = now;
+                timer.position = position;
+                timer.paused = paused;
+            }
+            _ => {
+                self.play_timer = Some(PlayTimer {
+                    item_id: item_id.clone(),
+                    played: Duration::ZERO,
+                    last_seen: now,
+                    position,
+                    paused,
+                });
+            }
+        }
+
+        self.play_timer.as_ref().unwrap().played.as_secs() >= self.min_play_time
+    }
+
+    /// Fetch the details of the playing item once per item
+    fn update_item_details(&mut self) {
+        let session = self.session.as_ref().unwrap();
+
+        if session.now_playing_item.media_type != MediaType::Music
+            && !session.now_playing_item.media_type.is_video()
+        {
+            return;
+        }
+
+        let item_id = &session.now_playing_item.id;
+
+        if self
+            .item_details
+            .as_ref()
+            .is_none_or(|details| &details.item_id != item_id)
+        {
+            let Some(user_id) = &session.user_id else {
+                return;
+            };
+
+            // Failures are cached too so a broken item isn't requested on every poll
+            let item = self.fetch_item(user_id, item_id).unwrap_or_else(|err| {
+                warn!("Failed to fetch item details: {}", err);
+                Item::default()
+            });
+
+            let series = session.now_playing_item.series_id.as_ref().and_then(|series_id| {
+                self.fetch_item(user_id, series_id)
+                    .inspect_err(|err| warn!("Failed to fetch series: {}", err))
+                    .ok()
+            });
+
+            self.item_details = Some(ItemDetails {
+                item_id: item_id.clone(),
+                item,
+                series,
+            });
+        }
+
+        let details = self.item_details.as_ref().unwrap();
+
+        self.session
+            .as_mut()
+            .unwrap()
+            .now_playing_item
+            .fill_details(&details.item);
+    }
+
+    /// Details of the playing item, `None` if they couldn't be fetched
+    fn item_details(&self) -> Option<&ItemDetails> {
+        let session = self.session.as_ref()?;
+
+        self.item_details
+            .as_ref()
+            .filter(|details| details.item_id == session.now_playing_item.id)
+    }
+
+    /// Reload the collections every hour while a video template uses `{collection}`,
+    /// fetching them takes a request per collection so it happens on another thread
+    fn update_collections(&mut self) {
+        if let Some(loading) = &self.collections_loading {
+            match loading.try_recv() {
+                Ok(collections) => {
+                    debug!("Loaded {} collection memberships", collections.len());
+                    self.collections = Some((collections, SystemTime::now()));
+                }
+                Err(TryRecvError::Empty) => return,
+                // Wait for the next expiration before trying again
+                Err(TryRecvError::Disconnected) => {
+                    self.collections = Some((HashMap::new(), SystemTime::now()))
+                }
+            }
+
+            self.collections_loading = None;
+        }
+
+        let session = self.session.as_ref().unwrap();
+
+        if !session.now_playing_item.media_type.is_video() || !self.uses_placeholder("{collection}")
+        {
+            return;
+        }
+
+        let expired = self.collections.as_ref().is_none_or(|(_, loaded)| {
+            SystemTime::now()
+                .duration_since(*loaded)
+                .map(|passed| passed.as_secs() > 3600)
+                .unwrap_or(false)
+        });
+
+        if !expired {
+            return;
+        }
+
+        let Some(user_id) = session.user_id.clone() else {
+            return;
+        };
+
+        let (sender, loading) = mpsc::channel();
+        let reqwest = self.reqwest.clone();
+        let url = self.url.clone();
+
+        std::thread::spawn(move || {
+            let collections = Self::fetch_collections(&reqwest, &url, &user_id)
+                .inspect_err(|err| warn!("Failed to fetch collections: {}", err))
+                .unwrap_or_default();
+            let _ = sender.send(collections);
+        });
+
+        self.collections_loading = Some(loading);
+    }
+
+    /// Maps the id of every item in a collection to the names of its collections,
+    /// collections whose items can't be fetched are left out
+    fn fetch_collections(
+        reqwest: &reqwest::blocking::Client,
+        url: &Url,
+        user_id: &str,
+    ) -> JfResult<HashMap<String, Vec<String>>> {
+        let collections: Items<ItemSummary> = fetch_json(
+            reqwest,
+            url,
+            &format!("Users/{}/Items?IncludeItemTypes=BoxSet&Recursive=true", user_id),
+        )?;
+
+        let mut memberships: HashMap<String, Vec<String>> = HashMap::new();
+
+        for collection in collections.items {
+            let Some(name) = collection.name else {
+                continue;
+            };
+
+            let children: Items<ItemSummary> = match fetch_json(
+                reqwest,
+                url,
+                &format!("Users/{}/Items?ParentId={}", user_id, collection.id),
+            ) {
+                Ok(children) => children,
+                Err(err) => {
+                    warn!("Failed to fetch the items of collection {}: {}", name, err);
+                    continue;
+                }
+            };
+
+            for child in children.items {
+                memberships.entry(child.id).or_default().push(name.clone());
+            }
+        }
+
+        Ok(memberships)
+    }
+
+    /// Find the SyncPlay group the user is watching with
+    fn update_syncplay_group(&mut self) {
+        self.syncplay_group = None;
+
+        if !self.syncplay_party_size
+            && !self.syncplay_join
+            && !self.uses_placeholder("{syncplay-group}")
+            && !self.uses_placeholder("{syncplay-members}")
+        {
+            return;
+        }
+
+        let Some(user_id) = self.session.as_ref().unwrap().user_id.as_ref() else {
+            return;
+        };
+
+        if self
+            .syncplay_groups
+            .as_ref()
+            .is_none_or(|(_, fetched)| fetched.elapsed().as_secs() >= 30)
+        {
+            let groups = self
+                .get_json::<Vec<SyncPlayGroup>>("SyncPlay/List")
+                .unwrap_or_else(|err| {
+                    debug!("Failed to fetch SyncPlay groups: {}", err);
+                    Vec::new()
+                });
+
+            self.syncplay_groups = Some((groups, Instant::now()));
+        }
+
+        let (groups, _) = self.syncplay_groups.as_ref().unwrap();
+
+        self.syncplay_group = groups
+            .iter()
+            .find(|group| {
+                group.participants.iter().any(|participant| {
+                    self.session_users.get(&participant.to_lowercase()) == Some(user_id)
+                })
+            })
+            .cloned();
     }
 
-    fn get_details(&self) -> String {
+    /// Find the album or playlist the queue was started from while a template uses `{playlist}`
+    fn update_queue_source(&mut self) {
         let session = self.session.as_ref().unwrap();
 
-        match session.now_playing_item.media_type {
-            MediaType::Music => {
-                let display_details_format = &self
-                    .music_display_options
-                    .display
-                    .details_text
-                    .as_ref()
-                    .unwrap();
-                self.parse_music_display(
-                    display_details_format
-                        .replace("{__default}", "{track}")
-                        .as_str(),
-                )
-            }
-            MediaType::Movie => {
-                let display_details_format = &self
-                    .movies_display_options
-                    .display
-                    .details_text
-                    .as_ref()
-                    .unwrap();
-                self.parse_movies_display(
-                    display_details_format
-                        .replace("{__default}", "{title}")
-                        .as_str(),
-                )
-            }
-            MediaType::Episode => {
-                let display_details_format = &self
-                    .episodes_display_options
-                    .display
-                    .details_text
-                    .as_ref()
-                    .unwrap();
-                self.parse_episodes_display(
-                    display_details_format
-                        .replace("{__default}", "{show-title}")
-                        .as_str(),
-                )
-            }
-            MediaType::AudioBook => session
-                .now_playing_item
-                .album
-                .as_ref()
-                .map(|a| a.to_string())
-                .unwrap_or_else(|| session.now_playing_item.name.to_string()),
-            _ => session.now_playing_item.name.to_string(),
+        if session.queue.len() < 2 || !self.uses_placeholder("{playlist}") {
+            return;
+        }
+
+        let queue_ids = session.sorted_queue_ids();
+
+        if self
+            .queue_source
+            .as_ref()
+            .is_some_and(|(ids, _)| ids == &queue_ids)
+        {
+            return;
         }
+
+        let Some(user_id) = &session.user_id else {
+            return;
+        };
+
+        let source = self
+            .find_queue_source(user_id, &queue_ids)
+            .unwrap_or_else(|err| {
+                warn!("Failed to find the playlist of the queue: {}", err);
+                None
+            });
+
+        debug!("Queue was started from {:?}", source);
+        self.queue_source = Some((queue_ids, source));
     }
 
-    fn get_state(&self) -> String {
+    /// Compares the queue with the album of the playing item and the user's playlists,
+    /// queues that were shuffled still match but instant mixes don't.
+    ///
+    /// Only the contents of playlists with as many items as the queue are fetched,
+    /// failing to fetch one of them only skips that playlist
+    fn find_queue_source(&self, user_id: &str, queue_ids: &[String]) -> JfResult<Option<String>> {
         let session = self.session.as_ref().unwrap();
 
-        match session.now_playing_item.media_type {
-            MediaType::Episode => {
-                let display_state_format = &self
-                    .episodes_display_options
-                    .display
-                    .state_text
-                    .as_ref()
-                    .unwrap();
-                self.parse_episodes_display(display_state_format.replace("{__default}", "").as_str())
-            }
-            MediaType::LiveTv => "Live TV".to_string(),
-            MediaType::Music => {
-                let display_state_format = &self
-                    .music_display_options
-                    .display
-                    .state_text
-                    .as_ref()
-                    .unwrap();
-                self.parse_music_display(
-                    display_state_format
-                        .replace("{__default}", "By {artists} {sep} ")
-                        .as_str(),
-                )
+        let matches = |items: &Items<ItemSummary>| {
+            let mut ids: Vec<&str> = items.items.iter().map(|item| item.id.as_str()).collect();
+            ids.sort();
+            ids == queue_ids
+        };
+
+        if let (Some(album_id), Some(album)) = (
+            &session.now_playing_item.album_id,
+            &session.now_playing_item.album,
+        ) {
+            match self.get_json(&format!("Users/{}/Items?ParentId={}", user_id, album_id)) {
+                Ok(tracks) if matches(&tracks) => return Ok(Some(album.clone())),
+                Ok(_) => (),
+                Err(err) => debug!("Failed to fetch the tracks of {}: {}", album, err),
             }
-            MediaType::Book => {
-                let mut state = String::new();
+        }
 
-                if let Some(position_ticks) = session.play_state.position_ticks {
-                    let ticks_to_pages = 10000;
+        let playlists: Items<ItemSummary> = self.get_json(&format!(
+            "Users/{}/Items?IncludeItemTypes=Playlist&Recursive=true&Fields=ChildCount",
+            user_id
+        ))?;
 
-                    let page = position_ticks / ticks_to_pages;
+        for playlist in playlists
+            .items
+            .into_iter()
+            .filter(|playlist| playlist.child_count.is_none_or(|count| count == queue_ids.len()))
+        {
+            match self.get_json(&format!("Playlists/{}/Items?UserId={}", playlist.id, user_id)) {
+                Ok(items) if matches(&items) => return Ok(playlist.name),
+                Ok(_) => (),
+                Err(err) => debug!("Failed to fetch playlist {}: {}", playlist.id, err),
+            }
+        }
 
-                    state += &format!("Reading page {}", page);
-                }
+        Ok(None)
+    }
 
-                state
-            }
-            MediaType::AudioBook => {
-                let mut state = String::new();
+    /// Replaces the placeholders describing the play queue and SyncPlay group
+    fn parse_queue(&self, input: &str) -> String {
+        let session = self.session.as_ref().unwrap();
+        let position = session.queue_position();
 
-                let artists = session.format_artists();
+        let playlist = self
+            .queue_source
+            .as_ref()
+            .filter(|(ids, _)| ids == &session.sorted_queue_ids())
+            .and_then(|(_, source)| source.clone())
+            .unwrap_or_default();
 
-                let genres = session
-                    .now_playing_item
-                    .genres
-                    .as_ref()
-                    .unwrap_or(&vec!["".to_string()])
-                    .join(", ");
+        let (syncplay_group, syncplay_members) = self
+            .syncplay_group
+            .as_ref()
+            .map(|group| (group.group_name.clone(), group.participants.join(", ")))
+            .unwrap_or_default();
 
-                if !artists.is_empty() {
-                    state += &format!("By {}", artists)
-                }
+        input
+            .replace("{syncplay-group}", &syncplay_group)
+            .replace("{syncplay-members}", &syncplay_members)
+            .replace("{playlist}", &playlist)
+            .replace(
+                "{queue-position}",
+                &position.map(|(p, _)| p.to_string()).unwrap_or_default(),
+            )
+            .replace(
+                "{queue-length}",
+                &position.map(|(_, l)| l.to_string()).unwrap_or_default(),
+            )
+    }
 
-                if !state.is_empty() && !genres.is_empty() {
-                    state += " - "
-                }
+    /// Names of the collections the playing item, or its series, belongs to
+    fn current_collections(&self) -> Vec<&str> {
+        let (Some(session), Some((collections, _))) = (&self.session, &self.collections) else {
+            return Vec::new();
+        };
 
-                state += &genres;
+        let item = &session.now_playing_item;
 
-                state
-            }
-            MediaType::Movie => {
-                let display_state_format = &self
-                    .movies_display_options
-                    .display
-                    .state_text
-                    .as_ref()
-                    .unwrap();
-                self.parse_movies_display(display_state_format.replace("{__default}", "").as_str())
-            }
-            _ => session
-                .now_playing_item
-                .genres
-                .as_ref()
-                .unwrap_or(&vec!["".to_string()])
-                .join(", "),
-        }
+        [Some(&item.id), item.series_id.as_ref()]
+            .into_iter()
+            .flatten()
+            .filter_map(|id| collections.get(id))
+            .flatten()
+            .map(|name| name.as_str())
+            .collect()
     }
 
-    fn get_image_text(&self) -> String {
+    /// Whether any of the templates, including the paused and last watched ones,
+    /// contain the placeholder
+    fn uses_placeholder(&self, placeholder: &str) -> bool {
+        [
+            &self.music_display_options,
+            &self.movies_display_options,
+            &self.episodes_display_options,
+            &self.books_display_options,
+            &self.tv_display_options,
+            &self.music_videos_display_options,
+            &self.audio_books_display_options,
+            &self.videos_display_options,
+            &self.trailers_display_options,
+            &self.recordings_display_options,
+            &self.tv_programs_display_options,
+            &self.photos_display_options,
+        ]
+        .into_iter()
+        .map(|options| &options.display)
+        .chain(self.paused_displays.values())
+        .chain([&self.last_watched_display])
+        .flat_map(|display| [&display.details_text, &display.state_text, &display.image_text])
+        .flatten()
+        .any(|text| text.contains(placeholder))
+    }
+
+    /// Fetch the lyrics once per track when the music lyrics are enabled
+    fn update_lyrics(&mut self) {
         let session = self.session.as_ref().unwrap();
 
-        match session.now_playing_item.media_type {
-            MediaType::Music => {
-                let display_image_format = &self
-                    .music_display_options
-                    .display
-                    .image_text
-                    .as_ref()
-                    .unwrap();
-                self.parse_music_display(display_image_format)
-            }
-            MediaType::Movie => {
-                let display_image_format = &self
-                    .movies_display_options
-                    .display
-                    .image_text
-                    .as_ref()
-                    .unwrap();
-                self.parse_movies_display(display_image_format)
+        if !self.music_lyrics || session.now_playing_item.media_type != MediaType::Music {
+            return;
+        }
+
+        let item_id = &session.now_playing_item.id;
+
+        if self.lyrics.as_ref().is_some_and(|(id, _)| id == item_id) {
+            return;
+        }
+
+        let lyrics = match self.get_json::<Lyrics>(&format!("Audio/{}/Lyrics", item_id)) {
+            Ok(lyrics) => Some(lyrics),
+            Err(JfError::JellyfinStatus {
+                status: StatusCode::NOT_FOUND,
+                ..
+            }) => {
+                debug!("Track has no lyrics");
+                None
             }
-            MediaType::Episode => {
-                let display_image_format = &self
-                    .episodes_display_options
-                    .display
-                    .image_text
-                    .as_ref()
-                    .unwrap();
-                self.parse_episodes_display(display_image_format)
+            Err(err) => {
+                warn!("Failed to fetch lyrics: {}", err);
+                None
             }
-            _ => "".to_string(),
-        }
+        };
+
+        self.lyrics = Some((item_id.clone(), lyrics));
     }
 
-    fn check_blacklist(&self) -> JfResult<bool> {
-        let session = self.session.as_ref().unwrap();
+    /// The lyric line at the current position of the playing track
+    fn current_lyric(&self) -> Option<&str> {
+        let session = self.session.as_ref()?;
+        let (item_id, lyrics) = self.lyrics.as_ref()?;
 
-        if self
-            .blacklist
-            .media_types
-            .iter()
-            .any(|m| m == &session.now_playing_item.media_type)
-        {
-            return Ok(true);
+        if item_id != &session.now_playing_item.id {
+            return None;
         }
 
-        if self.blacklist.check_item(&session.now_playing_item) {
-            return Ok(true);
-        }
+        lyrics.as_ref()?.line_at(session.live_position_ticks()?)
+    }
 
-        Ok(false)
+    /// Check whether the user has marked the series of the current episode as played
+    fn series_finished(&self) -> bool {
+        self.item_details()
+            .and_then(|details| details.series.as_ref()?.user_data.as_ref())
+            .is_some_and(|user_data| user_data.played)
     }
 
-    /// Fetch the virtual folder list and filter out the blacklisted libraries
-    fn fetch_blacklist(&self) -> JfResult<Vec<VirtualFolder>> {
-        let virtual_folders: Vec<VirtualFolder> = self
-            .reqwest
-            .get(self.url.join("Library/VirtualFolders")?)
-            .send()?
-            .json()?;
+    fn fetch_item(&self, user_id: &str, item_id: &str) -> JfResult<Item> {
+        self.get_json(&format!("Users/{}/Items/{}", user_id, item_id))
+    }
 
-        Ok(virtual_folders
-            .into_iter()
-            .filter(|library_folder| {
-                self.blacklist
-                    .libraries_names
-                    .contains(library_folder.name.as_ref().unwrap_or(&String::new()))
-            })
-            .collect())
+    /// Fetch the virtual folder list from Jellyfin
+    fn fetch_libraries(&self) -> JfResult<Vec<VirtualFolder>> {
+        self.get_json("Library/VirtualFolders")
+    }
+
+    /// Send a GET request to a Jellyfin endpoint and decode the JSON response
+    fn get_json<T: DeserializeOwned>(&self, path: &str) -> JfResult<T> {
+        fetch_json(&self.reqwest, &self.url, path)
     }
 
-    /// Reload the library list from Jellyfin and filter out the user-provided blacklisted libraries
-    fn reload_blacklist(&mut self) {
-        self.blacklist.libraries = match self.fetch_blacklist() {
-            Ok(blacklist) => BlacklistedLibraries::Initialized(blacklist, SystemTime::now()),
+    /// Reload the library list from Jellyfin and filter out the user-provided blacklisted and spoiler guarded libraries
+    fn reload_libraries(&mut self) {
+        match self.fetch_libraries() {
+            Ok(libraries) => {
+                self.blacklist.libraries =
+                    CachedLibraries::filtered(&libraries, &self.blacklist.libraries_names);
+                self.spoilers.libraries =
+                    CachedLibraries::filtered(&libraries, &self.spoilers.libraries_names);
+            }
             Err(err) => {
                 warn!("Failed to intialize blacklist: {}", err);
-                BlacklistedLibraries::Uninitialized
+                self.blacklist.libraries = CachedLibraries::Uninitialized;
+                self.spoilers.libraries = CachedLibraries::Uninitialized;
             }
         }
     }
@@ -868,10 +2469,10 @@ impl From<EpisodeDisplayOptions> for DisplayFormat {
 struct Blacklist {
     media_types: Vec<MediaType>,
     libraries_names: Vec<String>,
-    libraries: BlacklistedLibraries,
+    libraries: CachedLibraries,
 }
 
-enum BlacklistedLibraries {
+enum CachedLibraries {
     Uninitialized,
     Initialized(Vec<VirtualFolder>, SystemTime),
 }
@@ -880,26 +2481,82 @@ impl Blacklist {
     /// Check whether a [NowPlayingItem] is in a blacklisted library
     fn check_item(&self, playing_item: &NowPlayingItem) -> bool {
         debug!("Checking if an item is blacklisted: {}", playing_item.name);
-        self.check_path(playing_item.path.as_ref().unwrap_or(&String::new()))
+        self.libraries
+            .check_path(playing_item.path.as_ref().unwrap_or(&String::new()))
+    }
+}
+
+impl CachedLibraries {
+    /// Keep only the libraries whose name is in `names`
+    fn filtered(libraries: &[VirtualFolder], names: &[String]) -> Self {
+        CachedLibraries::Initialized(
+            libraries
+                .iter()
+                .filter(|library_folder| {
+                    names.contains(library_folder.name.as_ref().unwrap_or(&String::new()))
+                })
+                .cloned()
+                .collect(),
+            SystemTime::now(),
+        )
     }
 
-    /// Check whether a path is in a blacklisted library
+    /// Check whether a path is in one of the cached libraries
     fn check_path(&self, item_path: &str) -> bool {
-        match &self.libraries {
-            BlacklistedLibraries::Initialized(libraries, _) => {
+        match self {
+            CachedLibraries::Initialized(libraries, _) => {
                 debug!("Checking path: {}", item_path);
-                libraries.iter().any(|blacklisted_mf| {
-                    blacklisted_mf.locations.iter().any(|physical_folder| {
-                        debug!("BL path: {}", physical_folder);
+                libraries.iter().any(|library_folder| {
+                    library_folder.locations.iter().any(|physical_folder| {
+                        debug!("Library path: {}", physical_folder);
                         item_path.starts_with(physical_folder)
                     })
                 })
             }
-            BlacklistedLibraries::Uninitialized => false,
+            CachedLibraries::Uninitialized => false,
         }
     }
 }
 
+/// Same as [`Client::get_json`] for threads that don't have the client
+fn fetch_json<T: DeserializeOwned>(
+    reqwest: &reqwest::blocking::Client,
+    url: &Url,
+    path: &str,
+) -> JfResult<T> {
+    let body = reqwest.get(url.join(path)?).send()?.error_for_status()?.text()?;
+
+    Ok(serde_json::from_str(&body)?)
+}
+
+/// Continuous play time of an item, updated on every poll
+struct PlayTimer {
+    item_id: String,
+    played: Duration,
+    last_seen: Instant,
+    /// Playback position in seconds when last seen
+    position: Option<i64>,
+    paused: bool,
+}
+
+/// Item and series details fetched once per playing item
+struct ItemDetails {
+    item_id: String,
+    item: Item,
+    series: Option<Item>,
+}
+
+/// Hides episode titles (and optionally images) of series that friends may not have caught up on
+struct SpoilerGuard {
+    series: Vec<String>,
+    libraries_names: Vec<String>,
+    libraries: CachedLibraries,
+    min_age_days: Option<u64>,
+    mask: String,
+    hide_images: bool,
+    active: bool,
+}
+
 struct ImgBBOptions {
     enabled: bool,
     api_token: String,
@@ -925,9 +2582,57 @@ pub struct ClientBuilder {
     movies_display: DisplayFormat,
     episodes_separator: String,
     episodes_display: DisplayFormat,
+    books_separator: String,
+    books_display: DisplayFormat,
+    tv_separator: String,
+    tv_display: DisplayFormat,
+    music_videos_separator: String,
+    music_videos_display: DisplayFormat,
+    audio_books_separator: String,
+    audio_books_display: DisplayFormat,
+    videos_separator: String,
+    videos_display: DisplayFormat,
+    trailers_separator: String,
+    trailers_display: DisplayFormat,
+    recordings_separator: String,
+    recordings_display: DisplayFormat,
+    tv_programs_separator: String,
+    tv_programs_display: DisplayFormat,
+    photos_separator: String,
+    photos_display: DisplayFormat,
+    music_lyrics: bool,
+    music_lyrics_format: String,
+    ignored_extra_types: Vec<String>,
+    min_runtime: u64,
+    min_play_time: u64,
+    idle_browsing: bool,
+    idle_browsing_display: DisplayFormat,
+    last_watched_minutes: Option<u64>,
+    last_watched_display: DisplayFormat,
+    queue_party_size: bool,
+    syncplay_party_size: bool,
+    syncplay_join: bool,
+    syncplay_token: Option<String>,
+    syncplay_max_party_size: Option<u32>,
     blacklist_media_types: Vec<MediaType>,
     blacklist_libraries: Vec<String>,
+    spoiler_series: Vec<String>,
+    spoiler_libraries: Vec<String>,
+    spoiler_min_age_days: Option<u64>,
+    spoiler_mask: String,
+    spoiler_hide_images: bool,
     show_paused: bool,
+    timestamp_tolerance: u64,
+    timestamp_modes: HashMap<MediaType, TimestampMode>,
+    show_paused_duration: bool,
+    pause_timeout: Option<u64>,
+    paused_displays: HashMap<MediaType, DisplayFormat>,
+    show_pause_icon: bool,
+    profiles: HashMap<MediaType, Profile>,
+    application_switch_delay: u64,
+    audio_book_chapter_timestamps: bool,
+    utc_offset: Option<UtcOffset>,
+    time_format: String,
     show_images: bool,
     use_imgbb: bool,
     imgbb_api_token: String,
@@ -942,6 +2647,14 @@ pub struct ClientBuilder {
     default_music_image: Option<String>,
     default_audio_book_image: Option<String>,
     default_book_image: Option<String>,
+    default_music_video_image: Option<String>,
+    default_video_image: Option<String>,
+    default_trailer_image: Option<String>,
+    default_recording_image: Option<String>,
+    default_tv_program_image: Option<String>,
+    default_photo_image: Option<String>,
+    browsing_image: Option<String>,
+    last_watched_image: Option<String>,
 }
 
 impl ClientBuilder {
@@ -959,7 +2672,46 @@ impl ClientBuilder {
                 prefix: true,
                 simple: false,
             }),
+            books_separator: "-".to_string(),
+            books_display: DisplayFormat::from(Vec::new()),
+            tv_separator: "-".to_string(),
+            tv_display: DisplayFormat::from(Vec::new()),
+            music_videos_separator: "-".to_string(),
+            music_videos_display: DisplayFormat::from(vec!["genres".to_string()]),
+            audio_books_separator: "-".to_string(),
+            audio_books_display: DisplayFormat::from(vec!["genres".to_string()]),
+            videos_separator: "-".to_string(),
+            videos_display: DisplayFormat::from(Vec::new()),
+            trailers_separator: "-".to_string(),
+            trailers_display: DisplayFormat::from(Vec::new()),
+            recordings_separator: "-".to_string(),
+            recordings_display: DisplayFormat::from(Vec::new()),
+            tv_programs_separator: "-".to_string(),
+            tv_programs_display: DisplayFormat::from(Vec::new()),
+            photos_separator: "-".to_string(),
+            photos_display: DisplayFormat::from(Vec::new()),
+            spoiler_mask: "Hidden".to_string(),
+            music_lyrics: false,
+            music_lyrics_format: "{lyric}".to_string(),
+            ignored_extra_types: vec!["ThemeSong".to_string(), "ThemeVideo".to_string()],
+            idle_browsing_display: DisplayFormat {
+                details_text: Some("Browsing Jellyfin".to_string()),
+                state_text: Some("{client}".to_string()),
+                image_text: None,
+            },
+            last_watched_display: DisplayFormat {
+                details_text: Some("Last watched".to_string()),
+                state_text: Some("{__default}".to_string()),
+                image_text: None,
+            },
+            queue_party_size: false,
+            syncplay_party_size: false,
+            syncplay_join: false,
             show_paused: true,
+            show_pause_icon: true,
+            application_switch_delay: 10,
+            timestamp_tolerance: 5,
+            time_format: "[hour]:[minute]".to_string(),
             imgbb_image_expiration: 432000, // 5 days
             default_image: "https://i.imgur.com/oX6vcds.png".to_string(),
             pause_icon_image: "https://i.imgur.com/wlHSvYy.png".to_string(),
@@ -967,131 +2719,357 @@ impl ClientBuilder {
         }
     }
 
-    /// Jellyfin URL to be used by the client.
+    /// Jellyfin URL to be used by the client.
+    ///
+    /// Has no default.
+    pub fn url<T: Into<String>>(&mut self, url: T) -> &mut Self {
+        self.url = url.into();
+        self
+    }
+
+    /// Discord Application ID that the client will use when connecting to Discord,
+    /// unless the [Profile] of the playing media type sets another one.
+    ///
+    /// Defaults to `"1053747938519679018"`.
+    pub fn api_token<T: Into<String>>(&mut self, api_token: T) -> &mut Self {
+        self.api_token = api_token.into();
+        self
+    }
+
+    /// Jellyfin API Key that will be used to gather data about what is being played.
+    ///
+    /// Has no default.
+    pub fn api_key<T: Into<String>>(&mut self, api_key: T) -> &mut Self {
+        self.api_key = api_key.into();
+        self
+    }
+
+    /// Controls the use of certificate validation in reqwest.
+    ///
+    /// Defaults to `false`.
+    pub fn self_signed(&mut self, self_signed: bool) -> &mut Self {
+        self.self_signed = self_signed;
+        self
+    }
+
+    /// Usernames that should be matched when checking Jellyfin sessions.
+    ///
+    /// Has no default.
+    ///
+    /// # Warning
+    /// This overwrites the value set in `ClientBuilder::Username()`,
+    /// only one of these 2 should be used
+    pub fn usernames(&mut self, usernames: Vec<String>) -> &mut Self {
+        self.usernames = usernames;
+        self
+    }
+
+    /// same as `ClientBuilder::Usernames()` but will only accept a single username
+    ///
+    /// Has no default.
+    ///
+    /// # Warning
+    /// This overwrites the value set in `ClientBuilder::Usernames()`,
+    /// only one of these 2 should be used
+    pub fn username<T: Into<String>>(&mut self, username: T) -> &mut Self {
+        self.usernames = vec![username.into()];
+        self
+    }
+
+    /// buttons to be displayed on the activity.
+    /// Pass an empty `Vec::new()` to display no buttons
+    ///
+    /// Defaults to dynamic buttons generated from the Jellyfin session.
+    pub fn buttons(&mut self, buttons: Vec<Button>) -> &mut Self {
+        self.buttons = Some(buttons);
+        self
+    }
+
+    /// Splits season and episode numbers with a dash.
+    ///
+    /// Defaults to `false`.
+    ///
+    /// # Example
+    /// S1E1 Pilot -> S1 - E1 Pilot
+    pub fn episode_divider(&mut self, val: bool) -> &mut Self {
+        self.episode_divider = val;
+        self
+    }
+
+    /// Adds leading 0's to season and episode numbers.
+    ///
+    /// Defaults to `false`.
+    ///
+    /// # Example
+    /// S1E1 Pilot -> S01E01 Pilot
+    pub fn episode_prefix(&mut self, val: bool) -> &mut Self {
+        self.episode_prefix = val;
+        self
+    }
+
+    /// Removes the episode name from the activity.
+    ///
+    /// Defaults to `false`.
+    ///
+    /// # Example
+    /// S1E1 Pilot -> S1E1
+    pub fn episode_simple(&mut self, val: bool) -> &mut Self {
+        self.episode_simple = val;
+        self
+    }
+
+    pub fn music_separator<T: Into<String>>(&mut self, separator: T) -> &mut Self {
+        self.music_separator = separator.into();
+        self
+    }
+
+    pub fn music_display(&mut self, display: DisplayFormat) -> &mut Self {
+        self.music_display = display;
+        self
+    }
+
+    pub fn movies_separator<T: Into<String>>(&mut self, separator: T) -> &mut Self {
+        self.movies_separator = separator.into();
+        self
+    }
+
+    pub fn movies_display(&mut self, display: DisplayFormat) -> &mut Self {
+        self.movies_display = display;
+        self
+    }
+
+    pub fn episodes_separator<T: Into<String>>(&mut self, separator: T) -> &mut Self {
+        self.episodes_separator = separator.into();
+        self
+    }
+
+    pub fn episodes_display(&mut self, display: DisplayFormat) -> &mut Self {
+        self.episodes_display = display;
+        self
+    }
+
+    /// Show the current line of synced lyrics as the music state.
+    ///
+    /// Tracks without synced lyrics use the normal state.
+    ///
+    /// Use [`Client::advance_lyrics`] between calls to [`Client::set_activity`] to follow the song.
+    ///
+    /// Defaults to `false`.
+    pub fn music_lyrics(&mut self, val: bool) -> &mut Self {
+        self.music_lyrics = val;
+        self
+    }
+
+    /// Format of the music state while a lyric line is shown, supports the same
+    /// placeholders as the music display.
+    ///
+    /// Defaults to `"{lyric}"`.
+    pub fn music_lyrics_format<T: Into<String>>(&mut self, format: T) -> &mut Self {
+        self.music_lyrics_format = format.into();
+        self
+    }
+
+    /// Jellyfin `ExtraType`s that are never displayed, such as `"Trailer"` or `"Clip"`.
     ///
-    /// Has no default.
-    pub fn url<T: Into<String>>(&mut self, url: T) -> &mut Self {
-        self.url = url.into();
+    /// Defaults to `vec!["ThemeSong", "ThemeVideo"]`.
+    pub fn ignored_extra_types(&mut self, extra_types: Vec<String>) -> &mut Self {
+        self.ignored_extra_types = extra_types;
         self
     }
 
-    /// Discord Application ID that the client will use when connecting to Discord.
+    /// Don't display items with a runtime shorter than this many seconds.
     ///
-    /// Defaults to `"1053747938519679018"`.
-    pub fn api_token<T: Into<String>>(&mut self, api_token: T) -> &mut Self {
-        self.api_token = api_token.into();
+    /// Defaults to `0`.
+    pub fn min_runtime(&mut self, seconds: u64) -> &mut Self {
+        self.min_runtime = seconds;
         self
     }
 
-    /// Jellyfin API Key that will be used to gather data about what is being played.
+    /// Only display a new item once it has played for this many seconds,
+    /// the last activity is kept until then so pre-rolls don't replace it.
+    /// Time spent paused doesn't count and seeking starts over.
     ///
-    /// Has no default.
-    pub fn api_key<T: Into<String>>(&mut self, api_key: T) -> &mut Self {
-        self.api_key = api_key.into();
+    /// Defaults to `0`.
+    pub fn min_play_time(&mut self, seconds: u64) -> &mut Self {
+        self.min_play_time = seconds;
         self
     }
 
-    /// Controls the use of certificate validation in reqwest.
+    /// Show an activity while a Jellyfin client is open but nothing is playing.
     ///
     /// Defaults to `false`.
-    pub fn self_signed(&mut self, self_signed: bool) -> &mut Self {
-        self.self_signed = self_signed;
+    pub fn idle_browsing(&mut self, val: bool) -> &mut Self {
+        self.idle_browsing = val;
         self
     }
 
-    /// Usernames that should be matched when checking Jellyfin sessions.
-    ///
-    /// Has no default.
+    /// Template of the browsing activity, supports `{device}`, `{client}` and `{version}`.
     ///
-    /// # Warning
-    /// This overwrites the value set in `ClientBuilder::Username()`,
-    /// only one of these 2 should be used
-    pub fn usernames(&mut self, usernames: Vec<String>) -> &mut Self {
-        self.usernames = usernames;
+    /// Defaults to `"Browsing Jellyfin"` with the client as the state.
+    pub fn idle_browsing_display(&mut self, display: DisplayFormat) -> &mut Self {
+        self.idle_browsing_display = display;
         self
     }
 
-    /// same as `ClientBuilder::Usernames()` but will only accept a single username
-    ///
-    /// Has no default.
+    /// Keep showing the last item for this many minutes after playback stops.
     ///
-    /// # Warning
-    /// This overwrites the value set in `ClientBuilder::Usernames()`,
-    /// only one of these 2 should be used
-    pub fn username<T: Into<String>>(&mut self, username: T) -> &mut Self {
-        self.usernames = vec![username.into()];
+    /// Defaults to `None`.
+    pub fn last_watched_minutes(&mut self, minutes: Option<u64>) -> &mut Self {
+        self.last_watched_minutes = minutes;
         self
     }
 
-    /// buttons to be displayed on the activity.
-    /// Pass an empty `Vec::new()` to display no buttons
+    /// Template of the last watched activity, supports the placeholders of the item's
+    /// media type and `{__default}` for its regular details line.
+    /// The regular image text is used when `image_text` is missing.
     ///
-    /// Defaults to dynamic buttons generated from the Jellyfin session.
-    pub fn buttons(&mut self, buttons: Vec<Button>) -> &mut Self {
-        self.buttons = Some(buttons);
+    /// Defaults to `"Last watched"` with the item as the state.
+    pub fn last_watched_display(&mut self, display: DisplayFormat) -> &mut Self {
+        self.last_watched_display = display;
         self
     }
 
-    /// Splits season and episode numbers with a dash.
+    /// Show the position in the play queue as the Discord party size, rendered as "(3 of 12)".
     ///
     /// Defaults to `false`.
+    pub fn queue_party_size(&mut self, val: bool) -> &mut Self {
+        self.queue_party_size = val;
+        self
+    }
+
+    /// Show the number of people in the user's SyncPlay group as the Discord party size.
     ///
-    /// # Example
-    /// S1E1 Pilot -> S1 - E1 Pilot
-    pub fn episode_divider(&mut self, val: bool) -> &mut Self {
-        self.episode_divider = val;
+    /// Takes priority over [`ClientBuilder::queue_party_size`].
+    ///
+    /// Defaults to `false`.
+    pub fn syncplay_party_size(&mut self, val: bool) -> &mut Self {
+        self.syncplay_party_size = val;
         self
     }
 
-    /// Adds leading 0's to season and episode numbers.
+    /// Let Discord friends ask to join while in a SyncPlay group, accepting a request
+    /// makes their client join the same group. The join request replaces the buttons.
+    ///
+    /// Requires [`ClientBuilder::syncplay_token`], Discord also only offers to join parties
+    /// that aren't full, see [`ClientBuilder::syncplay_max_party_size`].
     ///
     /// Defaults to `false`.
+    pub fn syncplay_join(&mut self, val: bool) -> &mut Self {
+        self.syncplay_join = val;
+        self
+    }
+
+    /// Access token of the Jellyfin client that joins groups for [`ClientBuilder::syncplay_join`].
     ///
-    /// # Example
-    /// S1E1 Pilot -> S01E01 Pilot
-    pub fn episode_prefix(&mut self, val: bool) -> &mut Self {
-        self.episode_prefix = val;
+    /// Jellyfin adds the session that owns the token to the group, so an API key can't be used.
+    /// The token must belong to one of the configured usernames, for example the one the web
+    /// client keeps in its local storage, which is checked before every join.
+    ///
+    /// Default is `None`
+    pub fn syncplay_token<T: Into<String>>(&mut self, token: T) -> &mut Self {
+        self.syncplay_token = Some(token.into());
         self
     }
 
-    /// Removes the episode name from the activity.
+    /// Maximum party size shown next to the size of the SyncPlay group, raised to the group size
+    /// when the group is larger.
     ///
-    /// Defaults to `false`.
+    /// Discord hides "Ask to Join" for full parties, set this above the group size to keep it.
     ///
-    /// # Example
-    /// S1E1 Pilot -> S1E1
-    pub fn episode_simple(&mut self, val: bool) -> &mut Self {
-        self.episode_simple = val;
+    /// Default is `None`, showing the party as full.
+    pub fn syncplay_max_party_size(&mut self, size: Option<u32>) -> &mut Self {
+        self.syncplay_max_party_size = size;
         self
     }
 
-    pub fn music_separator<T: Into<String>>(&mut self, separator: T) -> &mut Self {
-        self.music_separator = separator.into();
+    pub fn books_separator<T: Into<String>>(&mut self, separator: T) -> &mut Self {
+        self.books_separator = separator.into();
         self
     }
 
-    pub fn music_display(&mut self, display: DisplayFormat) -> &mut Self {
-        self.music_display = display;
+    pub fn books_display(&mut self, display: DisplayFormat) -> &mut Self {
+        self.books_display = display;
         self
     }
 
-    pub fn movies_separator<T: Into<String>>(&mut self, separator: T) -> &mut Self {
-        self.movies_separator = separator.into();
+    pub fn tv_separator<T: Into<String>>(&mut self, separator: T) -> &mut Self {
+        self.tv_separator = separator.into();
         self
     }
 
-    pub fn movies_display(&mut self, display: DisplayFormat) -> &mut Self {
-        self.movies_display = display;
+    pub fn tv_display(&mut self, display: DisplayFormat) -> &mut Self {
+        self.tv_display = display;
         self
     }
 
-    pub fn episodes_separator<T: Into<String>>(&mut self, separator: T) -> &mut Self {
-        self.episodes_separator = separator.into();
+    pub fn music_videos_separator<T: Into<String>>(&mut self, separator: T) -> &mut Self {
+        self.music_videos_separator = separator.into();
         self
     }
 
-    pub fn episodes_display(&mut self, display: DisplayFormat) -> &mut Self {
-        self.episodes_display = display;
+    pub fn music_videos_display(&mut self, display: DisplayFormat) -> &mut Self {
+        self.music_videos_display = display;
+        self
+    }
+
+    pub fn audio_books_separator<T: Into<String>>(&mut self, separator: T) -> &mut Self {
+        self.audio_books_separator = separator.into();
+        self
+    }
+
+    /// Shares the placeholders of music, `{__default}` is the book and its authors
+    pub fn audio_books_display(&mut self, display: DisplayFormat) -> &mut Self {
+        self.audio_books_display = display;
+        self
+    }
+
+    pub fn videos_separator<T: Into<String>>(&mut self, separator: T) -> &mut Self {
+        self.videos_separator = separator.into();
+        self
+    }
+
+    pub fn videos_display(&mut self, display: DisplayFormat) -> &mut Self {
+        self.videos_display = display;
+        self
+    }
+
+    pub fn trailers_separator<T: Into<String>>(&mut self, separator: T) -> &mut Self {
+        self.trailers_separator = separator.into();
+        self
+    }
+
+    pub fn trailers_display(&mut self, display: DisplayFormat) -> &mut Self {
+        self.trailers_display = display;
+        self
+    }
+
+    pub fn recordings_separator<T: Into<String>>(&mut self, separator: T) -> &mut Self {
+        self.recordings_separator = separator.into();
+        self
+    }
+
+    pub fn recordings_display(&mut self, display: DisplayFormat) -> &mut Self {
+        self.recordings_display = display;
+        self
+    }
+
+    pub fn tv_programs_separator<T: Into<String>>(&mut self, separator: T) -> &mut Self {
+        self.tv_programs_separator = separator.into();
+        self
+    }
+
+    pub fn tv_programs_display(&mut self, display: DisplayFormat) -> &mut Self {
+        self.tv_programs_display = display;
+        self
+    }
+
+    pub fn photos_separator<T: Into<String>>(&mut self, separator: T) -> &mut Self {
+        self.photos_separator = separator.into();
+        self
+    }
+
+    pub fn photos_display(&mut self, display: DisplayFormat) -> &mut Self {
+        self.photos_display = display;
         self
     }
 
@@ -1111,6 +3089,47 @@ impl ClientBuilder {
         self
     }
 
+    /// Hide episode titles of these series unless the spoiler guard is lifted.
+    ///
+    /// Defaults to `Vec::new()`.
+    pub fn spoiler_series(&mut self, series: Vec<String>) -> &mut Self {
+        self.spoiler_series = series;
+        self
+    }
+
+    /// Hide episode titles of every series in these libraries unless the spoiler guard is lifted.
+    ///
+    /// Defaults to `Vec::new()`.
+    pub fn spoiler_libraries(&mut self, libraries: Vec<String>) -> &mut Self {
+        self.spoiler_libraries = libraries;
+        self
+    }
+
+    /// Lift the spoiler guard for episodes that premiered at least this many days ago.
+    /// The guard is also lifted when the series is marked as played.
+    ///
+    /// Defaults to `None`, episodes are hidden regardless of their age.
+    pub fn spoiler_min_age_days(&mut self, days: Option<u64>) -> &mut Self {
+        self.spoiler_min_age_days = days;
+        self
+    }
+
+    /// Text that replaces `{title}` and `{original-title}` while the spoiler guard is active.
+    ///
+    /// Defaults to `"Hidden"`.
+    pub fn spoiler_mask<T: Into<String>>(&mut self, mask: T) -> &mut Self {
+        self.spoiler_mask = mask.into();
+        self
+    }
+
+    /// Use the image of the series instead of the episode image while the spoiler guard is active.
+    ///
+    /// Defaults to `false`.
+    pub fn spoiler_hide_images(&mut self, val: bool) -> &mut Self {
+        self.spoiler_hide_images = val;
+        self
+    }
+
     /// Show activity when paused.
     ///
     /// Defaults to `true`.
@@ -1119,6 +3138,105 @@ impl ClientBuilder {
         self
     }
 
+    /// How many seconds the reported position may drift before the timestamps are re-based.
+    /// Larger deviations are treated as a seek.
+    ///
+    /// Defaults to `5`.
+    pub fn timestamp_tolerance(&mut self, seconds: u64) -> &mut Self {
+        self.timestamp_tolerance = seconds;
+        self
+    }
+
+    /// Which timestamps are sent to Discord for a [MediaType].
+    ///
+    /// Defaults to [TimestampMode::Both] for every media type.
+    pub fn timestamp_mode(&mut self, media_type: MediaType, mode: TimestampMode) -> &mut Self {
+        self.timestamp_modes.insert(media_type, mode);
+        self
+    }
+
+    /// Must have show_paused = true
+    /// Shows the time elapsed since the content was paused instead of no timestamps.
+    ///
+    /// Defaults to `false`.
+    pub fn show_paused_duration(&mut self, val: bool) -> &mut Self {
+        self.show_paused_duration = val;
+        self
+    }
+
+    /// Overrides the activity type, buttons, `show_paused`, image text, default image
+    /// and Discord Application ID for a [MediaType].
+    ///
+    /// Has no default.
+    pub fn profile(&mut self, media_type: MediaType, profile: Profile) -> &mut Self {
+        self.profiles.insert(media_type, profile);
+        self
+    }
+
+    /// How many seconds a media type has to play before switching to the Discord Application ID
+    /// of its [Profile], so quickly switching between media types doesn't reconnect each time.
+    ///
+    /// Defaults to `10`.
+    pub fn application_switch_delay(&mut self, seconds: u64) -> &mut Self {
+        self.application_switch_delay = seconds;
+        self
+    }
+
+    /// Must have show_paused = true
+    /// Clears the activity once the content has been paused for this many seconds.
+    ///
+    /// Defaults to `None`.
+    pub fn pause_timeout(&mut self, seconds: Option<u64>) -> &mut Self {
+        self.pause_timeout = seconds;
+        self
+    }
+
+    /// Must have show_paused = true
+    /// Template used instead of the regular display while a [MediaType] is paused,
+    /// such as `"Paused at {position} / {runtime}"`. Missing lines use the regular display.
+    ///
+    /// Has no default.
+    pub fn paused_display(&mut self, media_type: MediaType, display: DisplayFormat) -> &mut Self {
+        self.paused_displays.insert(media_type, display);
+        self
+    }
+
+    /// Must have show_paused = true
+    /// Shows [ClientBuilder::pause_image] as the small image while paused.
+    ///
+    /// Defaults to `true`.
+    pub fn show_pause_icon(&mut self, val: bool) -> &mut Self {
+        self.show_pause_icon = val;
+        self
+    }
+
+    /// Scope the timestamps of [MediaType::AudioBook] to the current chapter instead of the whole book.
+    ///
+    /// Defaults to `false`.
+    pub fn audio_book_chapter_timestamps(&mut self, val: bool) -> &mut Self {
+        self.audio_book_chapter_timestamps = val;
+        self
+    }
+
+    /// Fixed offset used for wall-clock placeholders such as `{ends-at}`.
+    ///
+    /// Defaults to the local offset of the system, read again for every placeholder so daylight
+    /// saving time changes apply. Reading it fails on some platforms once a program is
+    /// multi-threaded, the offset at the time the client was built is used then.
+    pub fn utc_offset(&mut self, offset: UtcOffset) -> &mut Self {
+        self.utc_offset = Some(offset);
+        self
+    }
+
+    /// Format of wall-clock placeholders such as `{ends-at}`,
+    /// see the [time book](https://time-rs.github.io/book/api/format-description.html) for the syntax.
+    ///
+    /// Defaults to `"[hour]:[minute]"`.
+    pub fn time_format<T: Into<String>>(&mut self, format: T) -> &mut Self {
+        self.time_format = format.into();
+        self
+    }
+
     /// Must have show_paused = true
     /// Changes the image used for the paused status icon
     /// 
@@ -1185,6 +3303,70 @@ impl ClientBuilder {
         self
     }
 
+    /// Image of the browsing activity
+    ///
+    /// Default is `None`
+    pub fn browsing_image(&mut self, img: Option<String>) -> &mut Self {
+        self.browsing_image = img;
+        self
+    }
+
+    /// Image of the last watched activity when the item has none or images are disabled
+    ///
+    /// Default is `None`
+    pub fn last_watched_image(&mut self, img: Option<String>) -> &mut Self {
+        self.last_watched_image = img;
+        self
+    }
+
+    /// Overrides the default image for [MediaType::MusicVideo]
+    ///
+    /// Default is `None`
+    pub fn music_video_image(&mut self, img: Option<String>) -> &mut Self {
+        self.default_music_video_image = img;
+        self
+    }
+
+    /// Overrides the default image for [MediaType::Video]
+    ///
+    /// Default is `None`
+    pub fn video_image(&mut self, img: Option<String>) -> &mut Self {
+        self.default_video_image = img;
+        self
+    }
+
+    /// Overrides the default image for [MediaType::Trailer]
+    ///
+    /// Default is `None`
+    pub fn trailer_image(&mut self, img: Option<String>) -> &mut Self {
+        self.default_trailer_image = img;
+        self
+    }
+
+    /// Overrides the default image for [MediaType::Recording]
+    ///
+    /// Default is `None`
+    pub fn recording_image(&mut self, img: Option<String>) -> &mut Self {
+        self.default_recording_image = img;
+        self
+    }
+
+    /// Overrides the default image for [MediaType::TvProgram]
+    ///
+    /// Default is `None`
+    pub fn tv_program_image(&mut self, img: Option<String>) -> &mut Self {
+        self.default_tv_program_image = img;
+        self
+    }
+
+    /// Overrides the default image for [MediaType::Photo]
+    ///
+    /// Default is `None`
+    pub fn photo_image(&mut self, img: Option<String>) -> &mut Self {
+        self.default_photo_image = img;
+        self
+    }
+
     /// Show images from jellyfin on the activity.
     ///
     /// Defaults to `false`.
@@ -1254,7 +3436,11 @@ impl ClientBuilder {
     /// ```
     pub fn build(self) -> JfResult<Client> {
         if self.url.is_empty() || self.usernames.is_empty() || self.api_key.is_empty() {
-            return Err(Box::new(JfError::MissingRequiredValues));
+            return Err(JfError::MissingRequiredValues);
+        }
+
+        if self.syncplay_join && self.syncplay_token.as_ref().is_none_or(|t| t.is_empty()) {
+            return Err(JfError::MissingRequiredValues);
         }
 
         let mut headers = HeaderMap::new();
@@ -1265,51 +3451,28 @@ impl ClientBuilder {
         );
         headers.insert("X-Emby-Token", self.api_key.parse()?);
 
-        let default_episode_image;
-        let default_movie_image;
-        let default_tv_image;
-        let default_music_image;
-        let default_audio_book_image;
-        let default_book_image;
-
-        if let Some(url) = self.default_episode_image {
-            default_episode_image = url;
-        } else {
-            default_episode_image = self.default_image.clone();
-        }
-
-        if let Some(url) = self.default_movie_image {
-            default_movie_image = url;
-        } else {
-            default_movie_image = self.default_image.clone();
-        }
-
-        if let Some(url) = self.default_tv_image {
-            default_tv_image = url;
-        } else {
-            default_tv_image = self.default_image.clone();
-        }
-
-        if let Some(url) = self.default_music_image {
-            default_music_image = url;
-        } else {
-            default_music_image = self.default_image.clone();
-        }
-
-        if let Some(url) = self.default_audio_book_image {
-            default_audio_book_image = url;
-        } else {
-            default_audio_book_image = self.default_image.clone();
-        }
-
-        if let Some(url) = self.default_book_image {
-            default_book_image = url;
-        } else {
-            default_book_image = self.default_image.clone();
-        }
+        // Media types without their own default image use the global one
+        let or_default =
+            |image: Option<String>| image.unwrap_or_else(|| self.default_image.clone());
+        let default_episode_image = or_default(self.default_episode_image);
+        let default_movie_image = or_default(self.default_movie_image);
+        let default_tv_image = or_default(self.default_tv_image);
+        let default_music_image = or_default(self.default_music_image);
+        let default_audio_book_image = or_default(self.default_audio_book_image);
+        let default_book_image = or_default(self.default_book_image);
+        let default_music_video_image = or_default(self.default_music_video_image);
+        let default_video_image = or_default(self.default_video_image);
+        let default_trailer_image = or_default(self.default_trailer_image);
+        let default_recording_image = or_default(self.default_recording_image);
+        let default_tv_program_image = or_default(self.default_tv_program_image);
+        let default_photo_image = or_default(self.default_photo_image);
 
         Ok(Client {
-            discord_ipc_client: DiscordIpcClient::new(&self.api_token)?,
+            discord_ipc_client: DiscordIpcClient::new(&self.api_token).map_err(JfError::Discord)?,
+            application_id: self.api_token.clone(),
+            api_token: self.api_token,
+            pending_application_id: None,
+            application_switch_delay: self.application_switch_delay,
             url: self.url.parse()?,
             reqwest: reqwest::blocking::Client::builder()
                 .default_headers(headers)
@@ -1318,6 +3481,50 @@ impl ClientBuilder {
             usernames: self.usernames,
             buttons: self.buttons,
             session: None,
+            live_program: None,
+            lyrics: None,
+            item_details: None,
+            collections: None,
+            collections_loading: None,
+            queue_source: None,
+            queue_party_size: self.queue_party_size,
+            syncplay_group: None,
+            syncplay_groups: None,
+            session_users: HashMap::new(),
+            syncplay_party_size: self.syncplay_party_size,
+            syncplay_join: self.syncplay_join,
+            syncplay_token: self.syncplay_token,
+            syncplay_max_party_size: self.syncplay_max_party_size,
+            join_listener: None,
+            music_lyrics: self.music_lyrics,
+            music_lyrics_format: self.music_lyrics_format,
+            ignored_extra_types: self.ignored_extra_types,
+            min_runtime: self.min_runtime,
+            min_play_time: self.min_play_time,
+            play_timer: None,
+            browsing_session: None,
+            last_watched: None,
+            shown_item: None,
+            idle_browsing: self.idle_browsing,
+            idle_browsing_display: self.idle_browsing_display,
+            last_watched_minutes: self.last_watched_minutes,
+            last_watched_display: self.last_watched_display,
+            last_update: None,
+            last_sent: None,
+            rate_limiter: TokenBucket::discord(),
+            playback_anchor: None,
+            timestamp_tolerance: self.timestamp_tolerance as i64,
+            timestamp_modes: self.timestamp_modes,
+            show_paused_duration: self.show_paused_duration,
+            pause_timeout: self.pause_timeout,
+            paused_displays: self.paused_displays,
+            show_pause_icon: self.show_pause_icon,
+            profiles: self.profiles,
+            audio_book_chapter_timestamps: self.audio_book_chapter_timestamps,
+            paused_since: None,
+            utc_offset: self.utc_offset,
+            local_offset: UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC),
+            time_format: time::format_description::parse_owned::<2>(&self.time_format)?,
             music_display_options: DisplayOptions {
                 separator: self.music_separator,
                 display: self.music_display,
@@ -1330,10 +3537,55 @@ impl ClientBuilder {
                 separator: self.episodes_separator,
                 display: self.episodes_display,
             },
+            books_display_options: DisplayOptions {
+                separator: self.books_separator,
+                display: self.books_display,
+            },
+            tv_display_options: DisplayOptions {
+                separator: self.tv_separator,
+                display: self.tv_display,
+            },
+            music_videos_display_options: DisplayOptions {
+                separator: self.music_videos_separator,
+                display: self.music_videos_display,
+            },
+            audio_books_display_options: DisplayOptions {
+                separator: self.audio_books_separator,
+                display: self.audio_books_display,
+            },
+            videos_display_options: DisplayOptions {
+                separator: self.videos_separator,
+                display: self.videos_display,
+            },
+            trailers_display_options: DisplayOptions {
+                separator: self.trailers_separator,
+                display: self.trailers_display,
+            },
+            recordings_display_options: DisplayOptions {
+                separator: self.recordings_separator,
+                display: self.recordings_display,
+            },
+            tv_programs_display_options: DisplayOptions {
+                separator: self.tv_programs_separator,
+                display: self.tv_programs_display,
+            },
+            photos_display_options: DisplayOptions {
+                separator: self.photos_separator,
+                display: self.photos_display,
+            },
             blacklist: Blacklist {
                 media_types: self.blacklist_media_types,
                 libraries_names: self.blacklist_libraries,
-                libraries: BlacklistedLibraries::Uninitialized,
+                libraries: CachedLibraries::Uninitialized,
+            },
+            spoilers: SpoilerGuard {
+                series: self.spoiler_series,
+                libraries_names: self.spoiler_libraries,
+                libraries: CachedLibraries::Uninitialized,
+                min_age_days: self.spoiler_min_age_days,
+                mask: self.spoiler_mask,
+                hide_images: self.spoiler_hide_images,
+                active: false,
             },
             show_paused: self.show_paused,
             show_images: self.show_images,
@@ -1351,6 +3603,14 @@ impl ClientBuilder {
             default_music_image,
             default_audio_book_image,
             default_book_image,
+            default_music_video_image,
+            default_video_image,
+            default_trailer_image,
+            default_recording_image,
+            default_tv_program_image,
+            default_photo_image,
+            browsing_image: self.browsing_image.unwrap_or_else(|| self.default_image.clone()),
+            last_watched_image: self.last_watched_image.unwrap_or(self.default_image),
         })
     }
 }
@@ -0,0 +1,256 @@
+use crate::jellyfin::{Button, MediaType, NowPlayingItem};
+use discord_rich_presence::activity::{
+    Activity, ActivityType as DiscordActivityType, Assets, Button as ActButton, Party, Secrets,
+    Timestamps,
+};
+use serde::{Deserialize, Serialize};
+
+/// Outcome of [`Client::set_activity`](crate::Client::set_activity).
+#[derive(Debug, Clone, PartialEq)]
+pub enum PresenceUpdate {
+    /// Something is playing and was sent to Discord.
+    Playing(PresenceSnapshot),
+    /// Something is paused and was sent to Discord, only returned when `show_paused` is enabled.
+    Paused(PresenceSnapshot),
+    /// Nothing is being played but a Jellyfin client is open, the browsing activity was sent to Discord.
+    Browsing(PresenceSnapshot),
+    /// Playback stopped recently and the last watched item was sent to Discord.
+    LastWatched(PresenceSnapshot),
+    /// Nothing is being played.
+    Idle,
+    /// Something is being played but shouldn't be displayed.
+    Hidden(HiddenReason),
+}
+
+impl PresenceUpdate {
+    /// Returns the snapshot if anything was sent to Discord.
+    pub fn snapshot(&self) -> Option<&PresenceSnapshot> {
+        match self {
+            PresenceUpdate::Playing(snapshot)
+            | PresenceUpdate::Paused(snapshot)
+            | PresenceUpdate::Browsing(snapshot)
+            | PresenceUpdate::LastWatched(snapshot) => Some(snapshot),
+            PresenceUpdate::Idle | PresenceUpdate::Hidden(_) => None,
+        }
+    }
+}
+
+/// Why a playing session isn't displayed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HiddenReason {
+    /// The content is paused and `show_paused` is disabled.
+    Paused,
+    /// The content has been paused for longer than the pause timeout.
+    PauseTimeout,
+    /// The content is in the blacklist.
+    Blacklisted,
+    /// The content hasn't played for the minimum play time yet and nothing was displayed before it.
+    MinPlayTime,
+}
+
+/// Which timestamps are sent to Discord while something is playing.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TimestampMode {
+    /// Only the start, Discord shows the elapsed time.
+    Elapsed,
+    /// Only the end, Discord shows the remaining time.
+    Remaining,
+    /// Both start and end, Discord shows a progress bar or countdown.
+    #[default]
+    Both,
+    /// No timestamps.
+    None,
+}
+
+impl TimestampMode {
+    /// Keeps the timestamps selected by the mode.
+    pub(crate) fn apply(self, start: i64, end: i64) -> (Option<i64>, Option<i64>) {
+        match self {
+            TimestampMode::Elapsed => (Some(start), None),
+            TimestampMode::Remaining => (None, Some(end)),
+            TimestampMode::Both => (Some(start), Some(end)),
+            TimestampMode::None => (None, None),
+        }
+    }
+}
+
+/// What Discord shows the user to be doing, such as "Watching".
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivityType {
+    /// "Playing", also what Discord shows when no type is set.
+    Playing,
+    /// "Listening to".
+    Listening,
+    /// "Watching".
+    Watching,
+    /// "Competing in".
+    Competing,
+}
+
+impl ActivityType {
+    /// Type used for a [MediaType] when its profile doesn't set one, `None` for books.
+    pub(crate) fn default_for(media_type: MediaType) -> Option<Self> {
+        match media_type {
+            MediaType::Book => None,
+            MediaType::Music | MediaType::AudioBook => Some(ActivityType::Listening),
+            _ => Some(ActivityType::Watching),
+        }
+    }
+}
+
+impl From<ActivityType> for DiscordActivityType {
+    fn from(activity_type: ActivityType) -> Self {
+        match activity_type {
+            ActivityType::Playing => DiscordActivityType::Playing,
+            ActivityType::Listening => DiscordActivityType::Listening,
+            ActivityType::Watching => DiscordActivityType::Watching,
+            ActivityType::Competing => DiscordActivityType::Competing,
+        }
+    }
+}
+
+/// Overrides the global settings for a single [MediaType], missing values use the global ones.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct Profile {
+    /// What Discord shows the user to be doing.
+    pub activity_type: Option<ActivityType>,
+    /// Buttons displayed on the activity.
+    pub buttons: Option<Vec<Button>>,
+    /// Show the activity while paused.
+    pub show_paused: Option<bool>,
+    /// Text shown when hovering the large image if the display has none.
+    pub image_text: Option<String>,
+    /// Image used when the item has none or images are disabled.
+    pub default_image: Option<String>,
+    /// Discord Application ID, its name is shown as what the user is watching or listening to.
+    pub application_id: Option<String>,
+}
+
+/// Everything that was rendered for the current activity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PresenceSnapshot {
+    /// Type of the content being played.
+    pub media_type: MediaType,
+    /// What Discord shows the user to be doing, not set when `None`.
+    pub activity_type: Option<ActivityType>,
+    /// The item as returned by Jellyfin.
+    pub item: NowPlayingItem,
+    /// First line of the activity.
+    pub details: String,
+    /// Second line of the activity.
+    pub state: String,
+    /// URL of the large image.
+    pub image_url: String,
+    /// Text shown when hovering the large image.
+    pub image_text: String,
+    /// URL of the small image, used for the pause icon.
+    pub small_image: Option<String>,
+    /// Text shown when hovering the small image.
+    pub small_text: Option<String>,
+    /// Buttons displayed on the activity.
+    pub buttons: Vec<Button>,
+    /// Unix timestamp of when playback started, in seconds.
+    pub start: Option<i64>,
+    /// Unix timestamp of when playback will end, in seconds.
+    pub end: Option<i64>,
+    /// Current and maximum party size, shown as "(1 of 2)" next to the state.
+    pub party_size: Option<[i32; 2]>,
+    /// Identifies the party, needed for the join secret to be used.
+    pub party_id: Option<String>,
+    /// Secret sent to friends who ask to join, replaces the buttons.
+    pub join_secret: Option<String>,
+    /// Whether anything differs from the previous update.
+    pub changed: bool,
+}
+
+impl PresenceSnapshot {
+    /// Compares everything except `changed`.
+    pub(crate) fn same_content(&self, other: &PresenceSnapshot) -> bool {
+        self.media_type == other.media_type
+            && self.activity_type == other.activity_type
+            && self.item.id == other.item.id
+            && self.details == other.details
+            && self.state == other.state
+            && self.image_url == other.image_url
+            && self.image_text == other.image_text
+            && self.small_image == other.small_image
+            && self.small_text == other.small_text
+            && self.buttons == other.buttons
+            && self.start == other.start
+            && self.end == other.end
+            && self.party_size == other.party_size
+            && self.party_id == other.party_id
+            && self.join_secret == other.join_secret
+    }
+
+    /// Builds the Discord activity from the rendered fields.
+    pub(crate) fn activity(&self) -> Activity<'_> {
+        let mut activity = Activity::new();
+
+        let mut assets = Assets::new()
+            .large_image(&self.image_url)
+            .large_text(&self.image_text);
+
+        if let Some(small_image) = &self.small_image {
+            assets = assets.small_image(small_image);
+        }
+
+        if let Some(small_text) = &self.small_text {
+            assets = assets.small_text(small_text);
+        }
+
+        let mut timestamps = Timestamps::new();
+
+        if let Some(start) = self.start {
+            timestamps = timestamps.start(start);
+        }
+
+        if let Some(end) = self.end {
+            timestamps = timestamps.end(end);
+        }
+
+        // Discord doesn't allow buttons and secrets on the same activity
+        if let Some(join_secret) = &self.join_secret {
+            activity = activity.secrets(Secrets::new().join(join_secret));
+        } else if !self.buttons.is_empty() {
+            activity = activity.buttons(
+                self.buttons
+                    .iter()
+                    .map(|b| ActButton::new(&b.name, &b.url))
+                    .collect(),
+            );
+        }
+
+        if self.party_size.is_some() || self.party_id.is_some() {
+            let mut party = Party::new();
+
+            if let Some(size) = self.party_size {
+                party = party.size(size);
+            }
+
+            if let Some(id) = &self.party_id {
+                party = party.id(id);
+            }
+
+            activity = activity.party(party);
+        }
+
+        if let Some(activity_type) = self.activity_type {
+            activity = activity.activity_type(activity_type.into());
+        }
+
+        activity
+            .timestamps(timestamps)
+            .assets(assets)
+            .details(&self.details)
+            .state(&self.state)
+    }
+}
+
+impl std::fmt::Display for PresenceSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} | {}", self.details, self.state)
+    }
+}
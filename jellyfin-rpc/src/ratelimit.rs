@@ -0,0 +1,61 @@
+use std::time::{Duration, Instant};
+
+/// Discord allows 5 activity updates every 20 seconds.
+const DISCORD_UPDATES: u32 = 5;
+const DISCORD_WINDOW: Duration = Duration::from_secs(20);
+
+/// Token bucket used to stay within Discord's activity update limits.
+pub(crate) struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(capacity: u32, window: Duration) -> Self {
+        Self {
+            capacity: capacity as f64,
+            tokens: capacity as f64,
+            refill_per_sec: capacity as f64 / window.as_secs_f64(),
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Bucket matching Discord's `SET_ACTIVITY` limits.
+    pub(crate) fn discord() -> Self {
+        Self::new(DISCORD_UPDATES, DISCORD_WINDOW)
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// Takes a token if one is available.
+    pub(crate) fn try_take(&mut self) -> bool {
+        self.try_take_spare(0)
+    }
+
+    /// Takes a token only if `reserve` tokens would still be available afterwards,
+    /// used for low priority updates.
+    pub(crate) fn try_take_spare(&mut self, reserve: u32) -> bool {
+        self.refill();
+
+        if self.tokens >= 1.0 + reserve as f64 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Takes a token even if none are available, used for updates that can't be delayed.
+    pub(crate) fn take(&mut self) {
+        self.refill();
+        self.tokens = (self.tokens - 1.0).max(0.0);
+    }
+}
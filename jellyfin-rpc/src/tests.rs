@@ -1,4 +1,53 @@
-use crate::ClientBuilder;
+use crate::{
+    jellyfin::{Button, Item, Lyrics, PlayTime, RawSession, Session},
+    ratelimit::TokenBucket,
+    ActivityType, Client, ClientBuilder, DisplayFormat, HiddenReason, ItemDetails, JfError,
+    MediaType, PresenceUpdate, Profile, TimestampMode,
+};
+use serde_json::{json, Value};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Session of the user `test` playing `item`, as returned by Jellyfin
+fn raw_session(item: Value, play_state: Value) -> RawSession {
+    serde_json::from_value(json!({
+        "UserName": "test",
+        "UserId": "user",
+        "NowPlayingItem": item,
+        "PlayState": play_state,
+    }))
+    .unwrap()
+}
+
+/// Session of the user `test` playing `item`
+fn session(item: Value, play_state: Value) -> Session {
+    raw_session(item, play_state).build()
+}
+
+/// Client that never reaches a server, for tests that prefill its caches
+fn offline_client(mut builder: ClientBuilder) -> Client {
+    builder
+        .api_key("a1b2c3d4")
+        .username("test")
+        .url("http://127.0.0.1:1/");
+
+    builder.build().unwrap()
+}
+
+/// Session of a movie that is 10 minutes in
+fn movie(paused: bool) -> Session {
+    session(
+        json!({
+            "Name": "Movie",
+            "Type": "Movie",
+            "Id": "movie",
+            "RunTimeTicks": 7200_i64 * 10_000_000,
+        }),
+        json!({
+            "IsPaused": paused,
+            "PositionTicks": 600_i64 * 10_000_000,
+        }),
+    )
+}
 
 #[test]
 fn build_client_error() {
@@ -19,7 +68,1252 @@ fn invalid_url() {
 
     let client = builder.build();
 
-    if let Ok(_) = client {
-        panic!("client constructed without a valid url!")
+    match client {
+        Err(JfError::Url(_)) => (),
+        Err(err) => panic!("expected an url error, got {:?}", err),
+        Ok(_) => panic!("client constructed without a valid url!"),
+    }
+}
+
+#[test]
+fn error_classification() {
+    assert!(!JfError::ContentBlacklist.is_transient());
+    assert!(status_error(502).is_transient());
+    assert!(!status_error(401).is_transient());
+    assert!(JfError::ImageHost(Box::new(JfError::Io(std::io::Error::other("timeout")))).is_transient());
+}
+
+/// Error of a Jellyfin response with the given status code
+fn status_error(status: u16) -> JfError {
+    let response = http::Response::builder().status(status).body("").unwrap();
+
+    reqwest::blocking::Response::from(response)
+        .error_for_status()
+        .unwrap_err()
+        .into()
+}
+
+#[test]
+fn status_error_keeps_source() {
+    let err = status_error(404);
+
+    assert!(matches!(
+        err,
+        JfError::JellyfinStatus {
+            status: reqwest::StatusCode::NOT_FOUND,
+            ..
+        }
+    ));
+    assert!(std::error::Error::source(&err).is_some());
+}
+
+#[test]
+fn token_bucket_limits_bursts() {
+    let mut bucket = TokenBucket::new(5, Duration::from_secs(20));
+
+    for _ in 0..5 {
+        assert!(bucket.try_take());
     }
+
+    assert!(!bucket.try_take());
+}
+
+#[test]
+fn timestamps_ignore_drift_but_follow_seeks() {
+    let session = |position_secs: i64| {
+        session(
+            json!({
+                "Name": "Movie",
+                "Type": "Movie",
+                "Id": "abc",
+                "RunTimeTicks": 7200_i64 * 10_000_000,
+            }),
+            json!({
+                "IsPaused": false,
+                "PositionTicks": position_secs * 10_000_000,
+            }),
+        )
+    };
+
+    let mut anchor = None;
+
+    let PlayTime::Some(start, _) = session(600).get_time(&mut anchor, 5).unwrap() else {
+        panic!("expected timestamps");
+    };
+
+    // Reporting lag of a couple of seconds keeps the anchored timestamps
+    let PlayTime::Some(drifted, _) = session(598).get_time(&mut anchor, 5).unwrap() else {
+        panic!("expected timestamps");
+    };
+    assert_eq!(start, drifted);
+
+    // Seeking forward re-bases them
+    let PlayTime::Some(seeked, _) = session(900).get_time(&mut anchor, 5).unwrap() else {
+        panic!("expected timestamps");
+    };
+    assert!(start - seeked >= 295);
+}
+
+#[test]
+fn format_duration() {
+    assert_eq!(Client::format_duration(42), "0:42");
+    assert_eq!(Client::format_duration(2530), "42:10");
+    assert_eq!(Client::format_duration(7080), "1:58:00");
+}
+
+#[test]
+fn current_chapter() {
+    let session = session(
+        json!({
+            "Name": "Book",
+            "Type": "AudioBook",
+            "Id": "abc",
+            "RunTimeTicks": 3000_i64 * 10_000_000,
+            "Chapters": [
+                { "Name": "One", "StartPositionTicks": 0 },
+                { "Name": "Two", "StartPositionTicks": 1000_i64 * 10_000_000 },
+                { "Name": "Three", "StartPositionTicks": 2000_i64 * 10_000_000 },
+            ],
+        }),
+        json!({
+            "IsPaused": false,
+            "PositionTicks": 1500_i64 * 10_000_000,
+        }),
+    );
+
+    let (index, chapter) = session.current_chapter().unwrap();
+    assert_eq!(index, 1);
+    assert_eq!(chapter.name.as_deref(), Some("Two"));
+    assert_eq!(session.chapter_bounds(), Some((1000, 2000)));
+}
+
+#[test]
+fn audio_book_display() {
+    let audio_book = |album: Option<&str>, artists: Vec<&str>| {
+        session(
+            json!({
+                "Name": "Part 1",
+                "Type": "AudioBook",
+                "Id": "abc",
+                "Album": album,
+                "Artists": artists,
+                "Genres": ["Fantasy"],
+                "RunTimeTicks": 3000_i64 * 10_000_000,
+                "Chapters": [
+                    { "Name": "One", "StartPositionTicks": 0 },
+                    { "Name": "Two", "StartPositionTicks": 1000_i64 * 10_000_000 },
+                ],
+            }),
+            json!({
+                "IsPaused": false,
+                "PositionTicks": 1500_i64 * 10_000_000,
+            }),
+        )
+    };
+
+    // The defaults match what audiobooks showed before they had display options
+    let mut client = offline_client(ClientBuilder::new());
+    client.session = Some(audio_book(Some("Book"), vec!["Author"]));
+    assert_eq!(client.get_details(), "Book");
+    assert_eq!(client.get_state(), "By Author - Fantasy");
+
+    client.session = Some(audio_book(None, Vec::new()));
+    assert_eq!(client.get_details(), "Part 1");
+    assert_eq!(client.get_state(), "Fantasy");
+
+    let mut builder = ClientBuilder::new();
+    builder.audio_books_separator("|").audio_books_display(DisplayFormat {
+        details_text: Some("{__default}".to_string()),
+        state_text: Some(
+            "{chapter} ({chapter-number}/{chapter-count}) {sep} {percent}".to_string(),
+        ),
+        image_text: Some("{remaining} left".to_string()),
+    });
+    let mut client = offline_client(builder);
+    client.session = Some(audio_book(Some("Book"), vec!["Author"]));
+    assert_eq!(client.get_details(), "Book");
+    assert_eq!(client.get_state(), "Two (2/2) | 50%");
+    assert_eq!(client.get_image_text(), "25:00 left");
+}
+
+#[test]
+fn book_progress() {
+    let session = |path: &str, run_time_ticks: Option<i64>| {
+        session(
+            json!({
+                "Name": "Book",
+                "Type": "Book",
+                "Id": "abc",
+                "Path": path,
+                "RunTimeTicks": run_time_ticks,
+            }),
+            json!({
+                "IsPaused": false,
+                "PositionTicks": 420_i64 * 10_000,
+            }),
+        )
+    };
+
+    let epub = session("/books/book.epub", None).book_progress();
+    assert_eq!(epub.page, None);
+    assert_eq!(epub.percent, Some(42));
+
+    let pdf = session("/books/book.pdf", None).book_progress();
+    assert_eq!(pdf.page, Some(420));
+    assert_eq!(pdf.percent, None);
+
+    let pdf = session("/books/book.pdf", Some(840 * 10_000)).book_progress();
+    assert_eq!(pdf.page_count, Some(840));
+    assert_eq!(pdf.percent, Some(50));
+}
+
+#[test]
+fn live_tv_program_timestamps() {
+    let session = session(
+        json!({
+            "Name": "Channel",
+            "Type": "TvChannel",
+            "Id": "abc",
+            "ChannelNumber": "7",
+            "CurrentProgram": {
+                "Name": "News",
+                "StartDate": "2024-01-01T20:00:00.0000000Z",
+                "EndDate": "2024-01-01T21:00:00.0000000Z",
+            },
+        }),
+        json!({
+            "IsPaused": false,
+        }),
+    );
+
+    let program = session.program.as_ref().unwrap();
+    assert_eq!(program.name, "News");
+    assert_eq!(program.start(), Some(1704139200));
+    assert_eq!(program.end(), Some(1704142800));
+
+    assert!(session.get_time(&mut None, 5).unwrap() == PlayTime::Some(1704139200, 1704142800));
+}
+
+#[test]
+fn live_tv_paused() {
+    let mut client = offline_client(ClientBuilder::new());
+    client.session = Some(session(
+        json!({
+            "Name": "Channel",
+            "Type": "TvChannel",
+            "Id": "abc",
+            "CurrentProgram": {
+                "Name": "News",
+                "StartDate": "2024-01-01T20:00:00.0000000Z",
+                "EndDate": "2024-01-01T21:00:00.0000000Z",
+            },
+        }),
+        json!({
+            "IsPaused": true,
+        }),
+    ));
+
+    // The program keeps airing, its timestamps would keep counting while paused
+    let PresenceUpdate::Paused(snapshot) = client.render_presence().unwrap() else {
+        panic!("expected a paused presence");
+    };
+    assert_eq!((snapshot.start, snapshot.end), (None, None));
+
+    client.show_paused = false;
+    assert!(matches!(
+        client.render_presence().unwrap(),
+        PresenceUpdate::Hidden(HiddenReason::Paused)
+    ));
+}
+
+#[test]
+fn synced_lyrics() {
+    let lyrics = serde_json::from_value::<Lyrics>(json!({
+        "Lyrics": [
+            { "Text": "First", "Start": 10_000_000 },
+            { "Text": "", "Start": 50_000_000 },
+            { "Text": "Second", "Start": 80_000_000 },
+        ],
+    }))
+    .unwrap();
+
+    assert_eq!(lyrics.line_at(0), None);
+    assert_eq!(lyrics.line_at(20_000_000), Some("First"));
+    assert_eq!(lyrics.line_at(60_000_000), None);
+    assert_eq!(lyrics.line_at(90_000_000), Some("Second"));
+
+    let unsynced = serde_json::from_value::<Lyrics>(json!({
+        "Lyrics": [{ "Text": "First" }],
+    }))
+    .unwrap();
+
+    assert_eq!(unsynced.line_at(20_000_000), None);
+}
+
+#[test]
+fn playback_info() {
+    let session = session(
+        json!({
+            "Name": "Movie",
+            "Type": "Movie",
+            "Id": "abc",
+            "MediaStreams": [
+                {
+                    "Type": "Video",
+                    "Index": 0,
+                    "Codec": "hevc",
+                    "Width": 3840,
+                    "Height": 1608,
+                    "VideoRange": "HDR",
+                    "VideoRangeType": "DOVIWithHDR10",
+                },
+                { "Type": "Audio", "Index": 1, "Codec": "truehd", "Channels": 8 },
+                { "Type": "Audio", "Index": 2, "Codec": "aac", "ChannelLayout": "stereo" },
+            ],
+        }),
+        json!({
+            "IsPaused": false,
+            "PlayMethod": "DirectPlay",
+            "AudioStreamIndex": 2,
+        }),
+    );
+
+    let video = session.media_stream("Video").unwrap();
+    assert_eq!(video.resolution().as_deref(), Some("4K"));
+    assert_eq!(video.hdr(), Some("DV"));
+
+    let audio = session.media_stream("Audio").unwrap();
+    assert_eq!(audio.codec.as_deref(), Some("aac"));
+    assert_eq!(audio.audio_channels().as_deref(), Some("Stereo"));
+
+    assert_eq!(session.play_method(), Some("Direct Play"));
+}
+
+#[test]
+fn replace_list() {
+    let cast = ["First", "Second", "Third", "Fourth"];
+
+    assert_eq!(
+        Client::replace_list("With {cast|limit:2}", "cast", &cast),
+        "With First, Second"
+    );
+    assert_eq!(
+        Client::replace_list("{cast}", "cast", &cast[..2]),
+        "First, Second"
+    );
+    assert_eq!(
+        Client::replace_list("{cast|limit:3} / {cast|limit:1}", "cast", &cast),
+        "First, Second, Third / First"
+    );
+}
+
+#[test]
+fn queue_position() {
+    let mut session = session(
+        json!({
+            "Name": "Track",
+            "Type": "Audio",
+            "Id": "b",
+        }),
+        json!({
+            "IsPaused": false,
+        }),
+    );
+
+    session.queue = serde_json::from_value(json!([
+        { "Id": "a", "PlaylistItemId": "playlistItem0" },
+        { "Id": "b", "PlaylistItemId": "playlistItem1" },
+        { "Id": "c", "PlaylistItemId": "playlistItem2" },
+        { "Id": "b", "PlaylistItemId": "playlistItem3" },
+    ]))
+    .unwrap();
+    session.playlist_item_id = Some("playlistItem3".to_string());
+
+    assert_eq!(session.queue_position(), Some((4, 4)));
+    assert_eq!(session.sorted_queue_ids(), ["a", "b", "b", "c"]);
+}
+
+#[test]
+fn media_types() {
+    for (item_type, media_type) in [
+        ("MusicVideo", MediaType::MusicVideo),
+        ("Video", MediaType::Video),
+        ("Trailer", MediaType::Trailer),
+        ("Recording", MediaType::Recording),
+        ("TvProgram", MediaType::TvProgram),
+        ("Photo", MediaType::Photo),
+        ("Folder", MediaType::None),
+    ] {
+        let session = session(
+            json!({
+                "Name": "Item",
+                "Type": item_type,
+                "Id": "a",
+            }),
+            json!({
+                "IsPaused": false,
+            }),
+        );
+
+        assert_eq!(session.now_playing_item.media_type, media_type);
+    }
+
+    assert!(MediaType::Recording.is_video());
+    assert!(!MediaType::Photo.is_video());
+}
+
+#[test]
+fn profile_activity_type() {
+    let profile = serde_json::from_value::<Profile>(json!({
+        "activity_type": "competing",
+        "show_paused": false,
+    }))
+    .unwrap();
+
+    assert_eq!(profile.activity_type, Some(ActivityType::Competing));
+    assert_eq!(profile.show_paused, Some(false));
+    assert_eq!(profile.buttons, None);
+
+    assert_eq!(ActivityType::default_for(MediaType::AudioBook), Some(ActivityType::Listening));
+    assert_eq!(ActivityType::default_for(MediaType::Book), None);
+}
+
+#[test]
+fn spoiler_guard() {
+    let mut builder = ClientBuilder::new();
+    builder.spoiler_series(vec!["Show".to_string()]);
+    let mut client = offline_client(builder);
+
+    for (series_played, title) in [(false, "Hidden"), (true, "Finale")] {
+        client.session = Some(session(
+            json!({
+                "Name": "Finale",
+                "Type": "Episode",
+                "Id": "episode",
+                "SeriesId": "series",
+                "SeriesName": "Show",
+            }),
+            json!({
+                "IsPaused": false,
+            }),
+        ));
+        client.item_details = Some(ItemDetails {
+            item_id: "episode".to_string(),
+            item: Item::default(),
+            series: serde_json::from_value(json!({ "UserData": { "Played": series_played } }))
+                .ok(),
+        });
+
+        client.update_spoiler_guard();
+
+        assert_eq!(client.spoilers.active, !series_played);
+        assert_eq!(client.parse_episodes_display("{title}"), title);
+    }
+}
+
+#[test]
+fn spoiler_guard_imgbb() {
+    let urls_location = std::env::temp_dir()
+        .join(format!("jellyfin-rpc-imgbb-{}.json", std::process::id()))
+        .to_string_lossy()
+        .to_string();
+    let uploaded = |id: &str| {
+        json!({
+            "id": id,
+            "url": format!("https://i.ibb.co/{}.png", id),
+            "expiration_from_unix_seconds": u32::MAX,
+        })
+    };
+    std::fs::write(
+        &urls_location,
+        json!([uploaded("series"), uploaded("episode")]).to_string(),
+    )
+    .unwrap();
+
+    let mut builder = ClientBuilder::new();
+    builder
+        .show_images(true)
+        .spoiler_hide_images(true)
+        .use_imgbb(true)
+        .imgbb_api_token("key")
+        .imgbb_urls_file_location(&urls_location);
+    let mut client = offline_client(builder);
+    client.session = Some(session(
+        json!({ "Name": "Finale", "Type": "Episode", "Id": "episode", "SeriesId": "series" }),
+        json!({ "IsPaused": false }),
+    ));
+    let default_image: url::Url = "https://example.com/default.png".parse().unwrap();
+
+    // The series image is uploaded like any other instead of linking to the Jellyfin server
+    client.spoilers.active = true;
+    assert_eq!(
+        client.item_image(default_image.clone()).as_str(),
+        "https://i.ibb.co/series.png"
+    );
+
+    // Without a series the episode's own image would spoil it
+    client.session = Some(session(
+        json!({ "Name": "Finale", "Type": "Episode", "Id": "episode" }),
+        json!({ "IsPaused": false }),
+    ));
+    assert_eq!(client.item_image(default_image.clone()), default_image);
+
+    client.spoilers.active = false;
+    assert_eq!(
+        client.item_image(default_image.clone()).as_str(),
+        "https://i.ibb.co/episode.png"
+    );
+
+    std::fs::remove_file(urls_location).unwrap();
+}
+
+#[test]
+fn presence_updates() {
+    let mut client = offline_client(ClientBuilder::new());
+
+    assert_eq!(client.render_presence().unwrap(), PresenceUpdate::Idle);
+
+    client.session = Some(movie(false));
+    let PresenceUpdate::Playing(snapshot) = client.render_presence().unwrap() else {
+        panic!("expected the movie to be playing");
+    };
+    assert_eq!(snapshot.item.name, "Movie");
+    assert!(snapshot.start.is_some());
+
+    client.session = Some(movie(true));
+    assert!(matches!(client.render_presence().unwrap(), PresenceUpdate::Paused(_)));
+
+    let mut builder = ClientBuilder::new();
+    builder.show_paused(false);
+    let mut client = offline_client(builder);
+
+    client.session = Some(movie(true));
+    assert_eq!(
+        client.render_presence().unwrap(),
+        PresenceUpdate::Hidden(HiddenReason::Paused)
+    );
+
+    let mut builder = ClientBuilder::new();
+    builder.blacklist_media_types(vec![MediaType::Movie]);
+    let mut client = offline_client(builder);
+
+    client.session = Some(movie(false));
+    assert_eq!(
+        client.render_presence().unwrap(),
+        PresenceUpdate::Hidden(HiddenReason::Blacklisted)
+    );
+}
+
+#[test]
+fn unchanged_snapshot_is_not_resent() {
+    let mut client = offline_client(ClientBuilder::new());
+
+    client.session = Some(movie(false));
+    let update = client.render_presence().unwrap();
+    let mut snapshot = update.snapshot().unwrap().clone();
+    client.last_update = Some(update.clone());
+    client.last_sent = Some(snapshot.clone());
+
+    // Discord isn't connected, so anything that gets sent fails
+    let PresenceUpdate::Playing(unchanged) = client.publish(update).unwrap() else {
+        panic!("expected the movie to be playing");
+    };
+    assert!(!unchanged.changed);
+
+    snapshot.state = "Another state".to_string();
+    assert!(matches!(
+        client.publish(PresenceUpdate::Playing(snapshot.clone())),
+        Err(JfError::Discord(_))
+    ));
+
+    // Rate limited updates are still marked as changed
+    while client.rate_limiter.try_take() {}
+    let update = client.publish(PresenceUpdate::Playing(snapshot)).unwrap();
+    let PresenceUpdate::Playing(changed) = update else {
+        panic!("expected the movie to be playing");
+    };
+    assert!(changed.changed);
+}
+
+#[test]
+fn timestamp_modes() {
+    assert_eq!(TimestampMode::Elapsed.apply(10, 20), (Some(10), None));
+    assert_eq!(TimestampMode::Remaining.apply(10, 20), (None, Some(20)));
+    assert_eq!(TimestampMode::Both.apply(10, 20), (Some(10), Some(20)));
+    assert_eq!(TimestampMode::None.apply(10, 20), (None, None));
+
+    let mut builder = ClientBuilder::new();
+    builder.timestamp_mode(MediaType::Movie, TimestampMode::Remaining);
+    let mut client = offline_client(builder);
+
+    client.session = Some(movie(false));
+    let update = client.render_presence().unwrap();
+    let snapshot = update.snapshot().unwrap();
+    assert_eq!(snapshot.start, None);
+    assert!(snapshot.end.is_some());
+}
+
+#[test]
+fn paused_duration() {
+    for show_paused_duration in [false, true] {
+        let mut builder = ClientBuilder::new();
+        builder.show_paused_duration(show_paused_duration);
+        let mut client = offline_client(builder);
+
+        client.session = Some(movie(true));
+        let PresenceUpdate::Paused(snapshot) = client.render_presence().unwrap() else {
+            panic!("expected the movie to be paused");
+        };
+
+        let paused_since = client.paused_since.as_ref().map(|(_, since)| *since);
+        assert!(paused_since.is_some());
+        assert_eq!(snapshot.start, paused_since.filter(|_| show_paused_duration));
+        assert_eq!(snapshot.end, None);
+    }
+}
+
+/// Pretends the movie was paused `seconds` ago
+fn paused_ago(client: &mut Client, seconds: i64) {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+    client.paused_since = Some(("movie".to_string(), now - seconds));
+}
+
+#[test]
+fn pause_timeout() {
+    let mut builder = ClientBuilder::new();
+    builder.pause_timeout(Some(60));
+    let mut client = offline_client(builder);
+
+    client.session = Some(movie(true));
+    paused_ago(&mut client, 30);
+    assert!(matches!(client.render_presence().unwrap(), PresenceUpdate::Paused(_)));
+
+    paused_ago(&mut client, 90);
+    assert!(matches!(
+        client.render_presence().unwrap(),
+        PresenceUpdate::Hidden(HiddenReason::PauseTimeout)
+    ));
+
+    // Resuming starts the timeout over
+    client.session = Some(movie(false));
+    assert!(matches!(client.render_presence().unwrap(), PresenceUpdate::Playing(_)));
+    assert!(client.paused_since.is_none());
+
+    client.session = Some(movie(true));
+    assert!(matches!(client.render_presence().unwrap(), PresenceUpdate::Paused(_)));
+}
+
+#[test]
+fn paused_for() {
+    let mut client = offline_client(ClientBuilder::new());
+
+    client.session = Some(movie(true));
+    paused_ago(&mut client, 100);
+    assert!(client.parse_progress("{paused-for}").starts_with("1:4"));
+
+    paused_ago(&mut client, 3700);
+    assert!(client.parse_progress("{paused-for}").starts_with("1:01:4"));
+
+    // Only the item that was paused has a pause duration
+    client.paused_since = Some(("episode".to_string(), 0));
+    assert_eq!(client.parse_progress("{paused-for}"), "");
+
+    client.paused_since = None;
+    assert_eq!(client.parse_progress("{paused-for}"), "");
+}
+
+#[test]
+fn paused_displays() {
+    let mut builder = ClientBuilder::new();
+    builder.paused_display(
+        MediaType::Movie,
+        DisplayFormat {
+            state_text: Some("Paused for {paused-for}".to_string()),
+            ..Default::default()
+        },
+    );
+    builder.paused_display(
+        MediaType::Episode,
+        DisplayFormat {
+            details_text: Some("Paused episode".to_string()),
+            ..Default::default()
+        },
+    );
+    let mut client = offline_client(builder);
+
+    client.session = Some(movie(false));
+    let PresenceUpdate::Playing(playing) = client.render_presence().unwrap() else {
+        panic!("expected the movie to be playing");
+    };
+    assert!(!playing.state.starts_with("Paused"));
+
+    client.session = Some(movie(true));
+    paused_ago(&mut client, 100);
+    let PresenceUpdate::Paused(paused) = client.render_presence().unwrap() else {
+        panic!("expected the movie to be paused");
+    };
+
+    // Lines without a paused template keep the regular display
+    assert!(paused.state.starts_with("Paused for 1:4"));
+    assert_eq!(paused.details, playing.details);
+    assert_eq!(paused.image_text, playing.image_text);
+}
+
+#[test]
+fn user_data() {
+    let mut client = offline_client(ClientBuilder::new());
+
+    client.session = Some(movie(false));
+    client.item_details = Some(ItemDetails {
+        item_id: "movie".to_string(),
+        item: serde_json::from_value(json!({
+            "UserData": { "IsFavorite": true, "PlayCount": 3 },
+        }))
+        .unwrap(),
+        series: serde_json::from_value(json!({
+            "RecursiveItemCount": 24,
+            "UserData": { "UnplayedItemCount": 12 },
+        }))
+        .ok(),
+    });
+
+    assert_eq!(
+        client.parse_user_data("{favorite} {play-count} / {play-count-ordinal} rewatch"),
+        "❤️ 3 / 3rd rewatch"
+    );
+    assert_eq!(
+        client.parse_user_data("{series-watched} of {series-total} ({series-percent})"),
+        "12 of 24 (50%)"
+    );
+
+    // Details of another item are ignored
+    client.session = Some(session(
+        json!({ "Name": "Other", "Type": "Movie", "Id": "other" }),
+        json!({ "IsPaused": false }),
+    ));
+    assert_eq!(client.parse_user_data("{favorite}{play-count}{series-total}"), "");
+}
+
+#[test]
+fn ordinal() {
+    assert_eq!(Client::ordinal(1), "1st");
+    assert_eq!(Client::ordinal(2), "2nd");
+    assert_eq!(Client::ordinal(3), "3rd");
+    assert_eq!(Client::ordinal(4), "4th");
+    assert_eq!(Client::ordinal(11), "11th");
+    assert_eq!(Client::ordinal(12), "12th");
+    assert_eq!(Client::ordinal(22), "22nd");
+    assert_eq!(Client::ordinal(113), "113th");
+}
+
+/// Makes the user `test` watch a movie in the SyncPlay group `b` with a friend
+fn watch_together(client: &mut Client) {
+    client.session = Some(movie(false));
+    client.session_users = [("test", "user"), ("friend", "friend")]
+        .into_iter()
+        .map(|(name, id)| (name.to_string(), id.to_string()))
+        .collect();
+    client.syncplay_groups = Some((
+        serde_json::from_value(json!([
+            { "GroupId": "a", "GroupName": "Other", "Participants": ["Friend"] },
+            { "GroupId": "b", "GroupName": "Movie night", "Participants": ["Friend", "Test"] },
+        ]))
+        .unwrap(),
+        Instant::now(),
+    ));
+}
+
+#[test]
+fn syncplay_group() {
+    let mut builder = ClientBuilder::new();
+    builder.syncplay_party_size(true);
+    let mut client = offline_client(builder);
+
+    watch_together(&mut client);
+
+    client.update_syncplay_group();
+    assert_eq!(client.syncplay_group.as_ref().map(|group| group.group_id.as_str()), Some("b"));
+
+    // Participants without a known user id don't match
+    client.session_users.remove("test");
+    client.update_syncplay_group();
+    assert_eq!(client.syncplay_group, None);
+}
+
+#[test]
+fn syncplay_join_secret() {
+    let mut builder = ClientBuilder::new();
+    builder.syncplay_join(true);
+    assert!(matches!(builder.build(), Err(JfError::MissingRequiredValues)));
+
+    let mut builder = ClientBuilder::new();
+    builder
+        .syncplay_join(true)
+        .syncplay_token("t0k3n")
+        .syncplay_max_party_size(Some(4));
+    let mut client = offline_client(builder);
+
+    watch_together(&mut client);
+    let update = client.render_presence().unwrap();
+    let snapshot = update.snapshot().unwrap();
+    assert_eq!(snapshot.join_secret.as_deref(), Some("b"));
+    assert_eq!(snapshot.party_id.as_deref(), Some("b"));
+    assert_eq!(snapshot.party_size, Some([2, 4]));
+
+    let mut builder = ClientBuilder::new();
+    builder.syncplay_party_size(true);
+    let mut client = offline_client(builder);
+
+    watch_together(&mut client);
+    let update = client.render_presence().unwrap();
+    let snapshot = update.snapshot().unwrap();
+    assert_eq!(snapshot.join_secret, None);
+    assert_eq!(snapshot.party_size, Some([2, 2]));
+}
+
+/// Answers one request per response on a local port, returns its URL and the request lines
+fn serve(responses: Vec<&'static str>) -> (String, std::thread::JoinHandle<Vec<String>>) {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let server = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", server.local_addr().unwrap());
+
+    let requests = std::thread::spawn(move || {
+        let mut requests = Vec::new();
+
+        for body in responses {
+            let (stream, _) = server.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if let Some(length) = header.to_lowercase().strip_prefix("content-length:") {
+                    content_length = length.trim().parse().unwrap();
+                }
+                if header.trim().is_empty() {
+                    break;
+                }
+            }
+            reader.read_exact(&mut vec![0; content_length]).unwrap();
+
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+            requests.push(request_line.trim().to_string());
+        }
+
+        requests
+    });
+
+    (url, requests)
+}
+
+#[test]
+fn syncplay_join_while_idle() {
+    let mut builder = ClientBuilder::new();
+    builder.syncplay_join(true).syncplay_token("t0k3n");
+    let mut client = offline_client(builder);
+
+    // Nothing plays while the invite is accepted
+    assert!(client.session.is_none());
+
+    let (url, requests) = serve(vec![r#"{"Id":"user","Name":"Test"}"#, ""]);
+    client.url = url.parse().unwrap();
+    client.post_syncplay_join("b").unwrap();
+    assert_eq!(
+        requests.join().unwrap(),
+        ["GET /Users/Me HTTP/1.1", "POST /SyncPlay/Join HTTP/1.1"]
+    );
+
+    let (url, requests) = serve(vec![r#"{"Id":"friend","Name":"Friend"}"#]);
+    client.url = url.parse().unwrap();
+    assert!(matches!(client.post_syncplay_join("b"), Err(JfError::SyncPlayTokenUser)));
+    assert_eq!(requests.join().unwrap(), ["GET /Users/Me HTTP/1.1"]);
+}
+
+#[test]
+fn extras_are_skipped() {
+    let mut builder = ClientBuilder::new();
+    builder.min_runtime(60);
+    let mut client = offline_client(builder);
+
+    let video = |id: &str, extra_type: Option<&str>, runtime_secs: i64| {
+        raw_session(
+            json!({
+                "Name": id,
+                "Type": "Video",
+                "Id": id,
+                "ExtraType": extra_type,
+                "RunTimeTicks": runtime_secs * 10_000_000,
+            }),
+            json!({ "IsPaused": false }),
+        )
+    };
+
+    // Theme videos are ignored by default
+    client.select_session(vec![
+        video("theme", Some("ThemeVideo"), 600),
+        video("clip", None, 30),
+        video("movie", None, 600),
+    ]);
+    assert_eq!(
+        client.session.as_ref().unwrap().now_playing_item.id,
+        "movie"
+    );
+
+    client.select_session(vec![video("theme", Some("ThemeVideo"), 600)]);
+    assert!(client.session.is_none());
+}
+
+#[test]
+fn min_play_time() {
+    let mut builder = ClientBuilder::new();
+    builder.min_play_time(30);
+    let mut client = offline_client(builder);
+
+    let play = |client: &mut Client, paused: bool, position_secs: i64, secs_since_poll: u64| {
+        client.session = Some(session(
+            json!({ "Name": "Movie", "Type": "Movie", "Id": "movie" }),
+            json!({ "IsPaused": paused, "PositionTicks": position_secs * 10_000_000 }),
+        ));
+
+        if let Some(timer) = client.play_timer.as_mut() {
+            timer.last_seen -= Duration::from_secs(secs_since_poll);
+        }
+
+        client.played_long_enough()
+    };
+
+    assert!(!play(&mut client, false, 0, 0));
+    assert!(!play(&mut client, false, 20, 20));
+
+    // Time spent paused doesn't count
+    assert!(!play(&mut client, true, 20, 0));
+    assert!(!play(&mut client, true, 20, 600));
+    assert!(!play(&mut client, false, 20, 0));
+    assert!(play(&mut client, false, 32, 12));
+
+    // Seeking starts over
+    assert!(!play(&mut client, false, 900, 10));
+    assert!(!play(&mut client, false, 920, 20));
+    assert!(play(&mut client, false, 930, 10));
+}
+
+#[test]
+fn last_watched() {
+    let mut builder = ClientBuilder::new();
+    builder
+        .last_watched_minutes(Some(10))
+        .blacklist_media_types(vec![MediaType::Episode]);
+    let mut client = offline_client(builder);
+
+    let playing = |media_type: &str, id: &str| {
+        raw_session(
+            json!({
+                "Name": id,
+                "Type": media_type,
+                "Id": id,
+                "RunTimeTicks": 3600_i64 * 10_000_000,
+            }),
+            json!({ "IsPaused": false, "PositionTicks": 0 }),
+        )
+    };
+    let movie = || playing("Movie", "movie");
+    let episode = || playing("Episode", "episode");
+
+    client.select_session(vec![movie()]);
+    assert!(matches!(client.render_presence().unwrap(), PresenceUpdate::Playing(_)));
+    client.select_session(vec![]);
+    let PresenceUpdate::LastWatched(snapshot) = client.render_presence().unwrap() else {
+        panic!("expected the movie to be the last watched item");
+    };
+    assert_eq!(snapshot.item.id, "movie");
+
+    // Blacklisted items are never shown, so they don't replace it either
+    client.select_session(vec![episode()]);
+    assert!(matches!(
+        client.render_presence().unwrap(),
+        PresenceUpdate::Hidden(HiddenReason::Blacklisted)
+    ));
+    client.select_session(vec![]);
+    assert!(client.last_watched.is_none());
+    assert!(matches!(client.render_presence().unwrap(), PresenceUpdate::Idle));
+
+    // Neither are items that didn't play for the minimum play time
+    client.min_play_time = 60;
+    client.select_session(vec![playing("Video", "preroll")]);
+    assert!(matches!(
+        client.render_presence().unwrap(),
+        PresenceUpdate::Hidden(HiddenReason::MinPlayTime)
+    ));
+    client.select_session(vec![]);
+    assert!(client.last_watched.is_none());
+
+    // An item blacklisted after it was shown isn't displayed as last watched
+    client.min_play_time = 0;
+    client.select_session(vec![movie()]);
+    assert!(matches!(client.render_presence().unwrap(), PresenceUpdate::Playing(_)));
+    client.select_session(vec![]);
+    client.blacklist.media_types.push(MediaType::Movie);
+    assert!(matches!(client.render_presence().unwrap(), PresenceUpdate::Idle));
+}
+
+#[test]
+fn application_switch() {
+    let profile = serde_json::from_value::<Profile>(json!({ "application_id": "music" })).unwrap();
+    let mut builder = ClientBuilder::new();
+    builder
+        .profile(MediaType::Music, profile)
+        .application_switch_delay(10);
+    let mut client = offline_client(builder);
+
+    assert_eq!(client.pending_switch(MediaType::Movie), None);
+    assert!(client.pending_application_id.is_none());
+
+    // Waits for the delay before switching
+    assert_eq!(client.pending_switch(MediaType::Music), None);
+    assert!(client.pending_application_id.is_some());
+
+    // Going back to the current application before the delay cancels the switch
+    assert_eq!(client.pending_switch(MediaType::Movie), None);
+    assert!(client.pending_application_id.is_none());
+
+    assert_eq!(client.pending_switch(MediaType::Music), None);
+    client.pending_application_id.as_mut().unwrap().1 -= Duration::from_secs(5);
+    assert_eq!(client.pending_switch(MediaType::Music), None);
+    client.pending_application_id.as_mut().unwrap().1 -= Duration::from_secs(5);
+    assert_eq!(client.pending_switch(MediaType::Music), Some("music".to_string()));
+}
+
+#[cfg(unix)]
+#[test]
+fn join_listener_stops() {
+    use crate::join::JoinListener;
+    use std::{io::Read, os::unix::net::UnixListener};
+
+    let dir = std::env::temp_dir().join(format!("jellyfin-rpc-join-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let _ = std::fs::remove_file(dir.join("discord-ipc-0"));
+    let server = UnixListener::bind(dir.join("discord-ipc-0")).unwrap();
+    std::env::set_var("XDG_RUNTIME_DIR", &dir);
+
+    let discord = std::thread::spawn(move || {
+        let (mut socket, _) = server.accept().unwrap();
+        let mut header = [0; 8];
+        socket.read_exact(&mut header).unwrap();
+        let mut handshake = vec![0; u32::from_le_bytes(header[4..].try_into().unwrap()) as usize];
+        socket.read_exact(&mut handshake).unwrap();
+
+        let ready = json!({ "cmd": "DISPATCH", "evt": "READY" }).to_string();
+        let mut reply = 1_u32.to_le_bytes().to_vec();
+        reply.extend((ready.len() as u32).to_le_bytes());
+        reply.extend(ready.as_bytes());
+        std::io::Write::write_all(&mut socket, &reply).unwrap();
+
+        // Reads until the listener closes the connection
+        let mut rest = Vec::new();
+        socket.read_to_end(&mut rest).unwrap();
+    });
+
+    let listener = JoinListener::start("application").unwrap();
+
+    // Gives the thread time to block on reading events
+    std::thread::sleep(Duration::from_millis(300));
+    assert!(listener.is_running());
+
+    let stopping = Instant::now();
+    drop(listener);
+    assert!(stopping.elapsed() < Duration::from_secs(2));
+
+    discord.join().unwrap();
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn profile_buttons() {
+    let button = |name: &str, url: &str| Button::new(name.to_string(), url.to_string());
+    let profile = serde_json::from_value::<Profile>(json!({
+        "buttons": [
+            { "name": "dynamic", "url": "dynamic" },
+            { "name": "Profile", "url": "https://example.com/profile" },
+        ],
+    }))
+    .unwrap();
+    let mut builder = ClientBuilder::new();
+    builder
+        .buttons(vec![button("Global", "https://example.com")])
+        .profile(MediaType::Movie, profile);
+    let mut client = offline_client(builder);
+
+    // Without external URLs the dynamic button is left out
+    client.session = Some(movie(false));
+    assert_eq!(
+        client.get_buttons(),
+        Some(vec![button("Profile", "https://example.com/profile")])
+    );
+
+    client.session = Some(session(
+        json!({
+            "Name": "Movie",
+            "Type": "Movie",
+            "Id": "movie",
+            "ExternalUrls": [{ "Name": "IMDb", "Url": "https://imdb.com/title" }],
+        }),
+        json!({ "IsPaused": false }),
+    ));
+    assert_eq!(
+        client.get_buttons(),
+        Some(vec![
+            button("IMDb", "https://imdb.com/title"),
+            button("Profile", "https://example.com/profile"),
+        ])
+    );
+
+    // Other media types keep the global buttons
+    client.session = Some(session(
+        json!({ "Name": "Episode", "Type": "Episode", "Id": "episode" }),
+        json!({ "IsPaused": false }),
+    ));
+    assert_eq!(client.get_buttons(), Some(vec![button("Global", "https://example.com")]));
+}
+
+#[test]
+fn placeholder_in_other_templates() {
+    let client = offline_client(ClientBuilder::new());
+    assert!(!client.uses_placeholder("{collection}"));
+
+    let mut builder = ClientBuilder::new();
+    builder.paused_display(
+        MediaType::Movie,
+        DisplayFormat {
+            state_text: Some("Paused in {collection}".to_string()),
+            ..Default::default()
+        },
+    );
+    assert!(offline_client(builder).uses_placeholder("{collection}"));
+
+    let mut builder = ClientBuilder::new();
+    builder.last_watched_display(DisplayFormat {
+        details_text: Some("Last watched {collection}".to_string()),
+        ..Default::default()
+    });
+    assert!(offline_client(builder).uses_placeholder("{collection}"));
+}
+
+#[test]
+fn collections_load_in_background() {
+    let mut builder = ClientBuilder::new();
+    builder.movies_display(DisplayFormat {
+        details_text: Some("{title}".to_string()),
+        state_text: Some("{collection}".to_string()),
+        image_text: None,
+    });
+    let mut client = offline_client(builder);
+    client.session = Some(movie(false));
+
+    let (url, requests) = serve(vec![
+        r#"{"Items":[{"Id":"saga","Name":"Saga"},{"Id":"other","Name":"Other"}]}"#,
+        r#"{"Items":[{"Id":"movie"},{"Id":"sequel"}]}"#,
+        r#"{"Items":[{"Id":"sequel"}]}"#,
+    ]);
+    client.url = url.parse().unwrap();
+
+    // The poll doesn't wait for the requests
+    client.update_collections();
+    assert!(client.collections.is_none());
+    assert_eq!(client.parse_display("{collection}"), "");
+
+    assert_eq!(requests.join().unwrap().len(), 3);
+
+    let started = Instant::now();
+    while client.collections.is_none() && started.elapsed() < Duration::from_secs(5) {
+        std::thread::sleep(Duration::from_millis(10));
+        client.update_collections();
+    }
+    assert_eq!(client.current_collections(), ["Saga"]);
+    assert!(client.collections_loading.is_none());
+}
+
+#[test]
+fn music_video_metadata() {
+    let mut client = offline_client(ClientBuilder::new());
+    client.session = Some(session(
+        json!({
+            "Name": "Song",
+            "Type": "MusicVideo",
+            "Id": "video",
+            "Artists": ["Band"],
+            "People": [
+                { "Name": "Director", "Type": "Director" },
+                { "Name": "Singer", "Type": "Actor" },
+                { "Name": "Dancer", "Type": "Actor" },
+            ],
+        }),
+        json!({ "IsPaused": false }),
+    ));
+
+    assert_eq!(
+        client.parse_display("{track} by {artists}, directed by {director} with {cast|limit:1}"),
+        "Song by Band, directed by Director with Singer"
+    );
+}
+
+#[test]
+fn music_placeholders() {
+    let template = "{album-artist} | {track-number} | {disc-number} | {composer} | {label} | \
+                    {bitrate} | {container} | {codec} | {lossless}";
+    let mut client = offline_client(ClientBuilder::new());
+
+    client.session = Some(session(
+        json!({
+            "Name": "Song",
+            "Type": "Audio",
+            "Id": "song",
+            "AlbumArtist": "Band",
+            "IndexNumber": 4,
+            "ParentIndexNumber": 2,
+            "People": [
+                { "Name": "Writer", "Type": "Composer" },
+                { "Name": "Producer", "Type": "Producer" },
+            ],
+            "Studios": [{ "Name": "Label" }],
+            "Container": "flac",
+            "MediaStreams": [
+                { "Type": "Audio", "Index": 0, "Codec": "mp3", "BitRate": 320000 },
+                { "Type": "Audio", "Index": 1, "Codec": "flac", "BitRate": 1411000 },
+            ],
+        }),
+        json!({ "IsPaused": false, "AudioStreamIndex": 1 }),
+    ));
+    assert_eq!(
+        client.parse_display(template),
+        "Band | 4 | 2 | Writer | Label | 1411 kbps | FLAC | FLAC | Lossless"
+    );
+
+    // The first audio stream is used when none is selected, lossy codecs aren't labeled
+    client.session = Some(session(
+        json!({
+            "Name": "Song",
+            "Type": "Audio",
+            "Id": "song",
+            "MediaStreams": [{ "Type": "Audio", "Index": 0, "Codec": "mp3", "BitRate": 320000 }],
+        }),
+        json!({ "IsPaused": false }),
+    ));
+    assert_eq!(client.parse_display("{bitrate} {codec} {lossless}"), "320 kbps MP3");
+
+    // Missing fields leave their placeholders empty
+    client.session = Some(session(
+        json!({ "Name": "Song", "Type": "Audio", "Id": "song" }),
+        json!({ "IsPaused": false }),
+    ));
+    assert_eq!(client.parse_display(template), "| | | | | | | |");
 }